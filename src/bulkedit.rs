@@ -0,0 +1,186 @@
+use std::collections::{HashMap, HashSet};
+
+use regex::Regex;
+
+use crate::database::Todo;
+
+const HEADER: &str = "\
+# Edit the todos below, then save and quit to apply the changes.
+# One todo per line: id, priority, [ ] or [x] for done, and title.
+# Lines without an id are added, e.g. `0 [ ] Buy milk` or `[ ] Buy milk`, removed lines are deleted.
+# Lines starting with # are ignored, leave the file unchanged to cancel.
+";
+
+// A todo line of the edited text, without an id for todos to add
+#[derive(Debug, PartialEq)]
+pub struct Line {
+    pub(crate) id: Option<i32>,
+    pub(crate) priority: i32,
+    pub(crate) done: bool,
+    pub(crate) title: String,
+}
+
+// What applying the edited text changes, updated todos are paired with their state before the edit
+#[derive(Debug, Default)]
+pub struct Changes {
+    pub(crate) updated: Vec<(Todo, Todo)>,
+    pub(crate) added: Vec<Todo>,
+    pub(crate) removed: Vec<Todo>,
+}
+
+impl Changes {
+    pub fn is_empty(&self) -> bool {
+        self.updated.is_empty() && self.added.is_empty() && self.removed.is_empty()
+    }
+}
+
+pub fn render(todos: &[Todo]) -> String {
+    let mut text = HEADER.to_string();
+    text.push('\n');
+    for todo in todos {
+        text.push_str(&format!("{:>4} {:>3} [{}] {}\n", todo.id, todo.priority, if todo.done { "x" } else { " " }, todo.title));
+    }
+    text
+}
+
+// Every line that isn't a todo is reported with its number, so nothing is applied from a mangled file
+pub fn parse(text: &str) -> Result<Vec<Line>, String> {
+    let line_regex = Regex::new(r"^(?:(\d+)\s+)?(-?\d+)\s+\[([ xX])\]\s*(.*?)\s*$").unwrap();
+    let added_regex = Regex::new(r"^\[([ xX])\]\s*(.*?)\s*$").unwrap();
+
+    let mut lines = Vec::new();
+    let mut errors = Vec::new();
+    for (number, text) in text.lines().enumerate() {
+        let text = text.trim();
+        if text.is_empty() || text.starts_with('#') {
+            continue;
+        }
+
+        let line = if let Some(captures) = line_regex.captures(text) {
+            match (captures.get(1).map(|id| id.as_str().parse()).transpose(), captures[2].parse()) {
+                (Ok(id), Ok(priority)) => Some(Line { id, priority, done: &captures[3] != " ", title: captures[4].to_string() }),
+                _ => None,
+            }
+        } else {
+            added_regex
+                .captures(text)
+                .map(|captures| Line { id: None, priority: 0, done: &captures[1] != " ", title: captures[2].to_string() })
+        };
+
+        match line {
+            Some(line) if line.title.is_empty() => errors.push(format!("line {}: the title is empty", number + 1)),
+            Some(line) => lines.push(line),
+            None => errors.push(format!("line {}: expected `id priority [ ] title`, got {}", number + 1, text)),
+        }
+    }
+
+    if errors.is_empty() {
+        Ok(lines)
+    } else {
+        Err(errors.join("\n"))
+    }
+}
+
+// Compares the edited lines with the todos that were written out
+pub fn diff(todos: &[Todo], lines: Vec<Line>) -> Result<Changes, String> {
+    let by_id: HashMap<i32, &Todo> = todos.iter().map(|todo| (todo.id, todo)).collect();
+    let mut seen = HashSet::new();
+    let mut changes = Changes::default();
+
+    for line in lines {
+        let Some(id) = line.id else {
+            changes.added.push(Todo { done: line.done, priority: line.priority, ..Todo::new(0, &line.title) });
+            continue;
+        };
+
+        let Some(todo) = by_id.get(&id) else {
+            return Err(format!("Todo {} wasn't being edited, remove its id to add it as a new todo", id));
+        };
+        if !seen.insert(id) {
+            return Err(format!("Todo {} is listed more than once", id));
+        }
+
+        if todo.title != line.title || todo.priority != line.priority || todo.done != line.done {
+            let mut updated = (*todo).clone();
+            updated.title = line.title;
+            updated.priority = line.priority;
+            updated.done = line.done;
+            changes.updated.push(((*todo).clone(), updated));
+        }
+    }
+
+    changes.removed = todos.iter().filter(|todo| !seen.contains(&todo.id)).cloned().collect();
+    Ok(changes)
+}
+
+// One line per change, e.g. `~ 12 Buy milk: priority 0 -> 3, done`
+pub fn summarize(changes: &Changes) -> Vec<String> {
+    let mut summary = Vec::new();
+    for (before, after) in &changes.updated {
+        let mut details = Vec::new();
+        if before.title != after.title {
+            details.push(format!("title was {}", before.title));
+        }
+        if before.priority != after.priority {
+            details.push(format!("priority {} -> {}", before.priority, after.priority));
+        }
+        if before.done != after.done {
+            details.push(if after.done { "done" } else { "undone" }.to_string());
+        }
+        summary.push(format!("~ {} {}: {}", after.id, after.title, details.join(", ")));
+    }
+    for todo in &changes.added {
+        summary.push(format!("+ {}", todo.title));
+    }
+    for todo in &changes.removed {
+        summary.push(format!("- {} {}", todo.id, todo.title));
+    }
+    summary
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn todo(id: i32, priority: i32, done: bool, title: &str) -> Todo {
+        Todo { priority, done, ..Todo::new(id, title) }
+    }
+
+    #[test]
+    fn render_and_parse() {
+        let todos = vec![todo(1, 0, false, "Buy milk"), todo(12, 3, true, "Write [draft] report")];
+        let text = render(&todos);
+        assert!(text.ends_with("   1   0 [ ] Buy milk\n  12   3 [x] Write [draft] report\n"));
+
+        let lines = parse(&text).unwrap();
+        assert_eq!(lines[1], Line { id: Some(12), priority: 3, done: true, title: "Write [draft] report".to_string() });
+        assert!(diff(&todos, lines).unwrap().is_empty());
+
+        let lines = parse("2 [ ] Call mom\n[x] Pay rent\n-1 [ ] Someday").unwrap();
+        assert_eq!(lines[0], Line { id: None, priority: 2, done: false, title: "Call mom".to_string() });
+        assert_eq!(lines[1], Line { id: None, priority: 0, done: true, title: "Pay rent".to_string() });
+        assert_eq!(lines[2].priority, -1);
+
+        let error = parse("1 0 [ ] Buy milk\n12 3 Write report\n5 [ ]").unwrap_err();
+        assert_eq!(error.lines().count(), 2);
+        assert!(error.starts_with("line 2:"));
+    }
+
+    #[test]
+    fn diff_edits() {
+        let todos = vec![todo(1, 0, false, "Buy milk"), todo(2, 0, false, "Call mom"), todo(3, 1, false, "Old task")];
+        let lines = parse("1 2 [x] Buy oat milk\n2 0 [ ] Call mom\n1 [ ] Pay rent").unwrap();
+
+        let changes = diff(&todos, lines).unwrap();
+        assert_eq!(changes.updated.len(), 1);
+        assert_eq!(changes.added.len(), 1);
+        assert_eq!(changes.removed.iter().map(|todo| todo.id).collect::<Vec<_>>(), vec![3]);
+        assert_eq!(
+            summarize(&changes),
+            vec!["~ 1 Buy oat milk: title was Buy milk, priority 0 -> 2, done", "+ Pay rent", "- 3 Old task"]
+        );
+
+        assert!(diff(&todos, parse("9 0 [ ] Unknown").unwrap()).is_err());
+        assert!(diff(&todos, parse("1 0 [ ] Buy milk\n1 0 [ ] Buy milk").unwrap()).is_err());
+    }
+}
@@ -1,8 +1,25 @@
-use clap::{Parser, Subcommand};
+use clap::{Parser, Subcommand, ValueEnum};
+use clap_complete::{ArgValueCandidates, Shell};
+
+use crate::settings::DuplicatePolicy;
+use crate::shell::completion::{all_todo_candidates, done_todo_candidates, open_todo_candidates};
 
 #[derive(Parser)]
 #[command(author, version, about, long_about = None)]
 pub struct Cli {
+    #[arg(long, global = true, help = "Database file to use instead of the profile's")]
+    pub(crate) db: Option<String>,
+
+    #[arg(long, global = true, help = "Profile whose database to use, see tc profile ls")]
+    pub(crate) profile: Option<String>,
+
+    #[arg(short, long, global = true, help = "Use the global todos even inside a project directory")]
+    pub(crate) global: bool,
+
+    // Not global, clap would only keep the values given after the subcommand
+    #[arg(long, value_name = "KEY=VALUE", help = "Override a setting for this run, e.g. tc --set list.sort=date l")]
+    pub(crate) set: Vec<String>,
+
     #[command(subcommand)]
     pub(crate) command: Option<Command>,
 }
@@ -16,15 +33,22 @@ pub enum Command {
 
     #[command(name = "a", aliases = ["add"], about = "Add new todo")]
     Add {
+        #[arg(help = "Todo text, with optional !3 or p:3 priority, due:fri, #tag, @context and ~30m estimate")]
         todo: Vec<String>,
 
-        #[arg(short, long, help = "Priority of the todo")]
+        #[arg(short, long, help = "Priority of the todo, overrides !3 in the text")]
         priority: Option<i32>,
+
+        #[arg(long, help = "Add the text as the title as is, without parsing tokens")]
+        raw: bool,
+
+        #[arg(long, value_enum, help = "What to do when an open todo has the same title [default: add.on_duplicate setting, complete-previous]")]
+        on_duplicate: Option<DuplicatePolicy>,
     },
 
     #[command(name = "u", aliases = ["update"], about = "Update todo")]
     Update {
-        #[arg(help = "Todo id to update")]
+        #[arg(help = "Todo id to update", add = ArgValueCandidates::new(open_todo_candidates))]
         id: i32,
 
         #[arg(short = 't', long = "todo", help = "New todo text", num_args = 1..)]
@@ -53,31 +77,243 @@ pub enum Command {
 
     #[command(name = "d", aliases = ["done"], about = "Mark todo as done")]
     Done {
-        #[arg(help = "Todo ids to update")]
+        #[arg(help = "Todo ids to update", add = ArgValueCandidates::new(open_todo_candidates))]
         ids: Vec<i32>,
     },
 
+    #[allow(clippy::upper_case_acronyms)]
     #[command(name = "undone", about = "Mark todo as undone")]
     UNDONE {
-        #[arg(help = "Todo ids to update")]
+        #[arg(help = "Todo ids to update", add = ArgValueCandidates::new(done_todo_candidates))]
         ids: Vec<i32>,
     },
 
     #[command(name = "r", aliases = ["remove"], about = "Remove todo")]
     Remove {
-        #[arg(num_args(1..), help = "Todo ids to update")]
+        #[arg(num_args(1..), help = "Todo ids to update", add = ArgValueCandidates::new(open_todo_candidates))]
         ids: Vec<i32>,
 
-        #[arg(long, help = "Remove todos until given date. Pass the argument in yyyy-mm-dd format")]
+        #[arg(long, allow_hyphen_values = true, help = "Remove todos created until the given date, e.g. yesterday, last fri, -2w or 2026-01-31")]
         date: Option<String>,
     },
 
     #[command(name = "rs", aliases = ["reset"], about = "Reset todos")]
     Reset,
 
-    #[command(name = "t", aliases = ["timer"], about = "Start timer")]
+    #[command(name = "t", aliases = ["timer"], about = "Start timer", args_conflicts_with_subcommands = true, subcommand_negates_reqs = true)]
     Timer {
-        #[arg(help = "Minutes to run the timer")]
-        minutes: u64,
+        #[command(subcommand)]
+        action: Option<TimerAction>,
+
+        #[arg(required_unless_present_any = ["pomodoro", "stopwatch"], help = "Minutes to run the timer")]
+        minutes: Option<u64>,
+
+        #[arg(short, long, conflicts_with = "minutes", help = "Alternate work sessions and breaks")]
+        pomodoro: bool,
+
+        #[arg(short, long, conflicts_with_all = ["minutes", "pomodoro"], help = "Count up until stopped, l marks a lap")]
+        stopwatch: bool,
+
+        #[arg(long, requires = "pomodoro", conflicts_with = "minutes", help = "Minutes of each pomodoro work session [default: timer.work setting, 25]")]
+        work: Option<u64>,
+
+        #[arg(long, requires = "pomodoro", conflicts_with = "minutes", help = "Minutes of the break after each work session [default: timer.short_break setting, 5]")]
+        short_break: Option<u64>,
+
+        #[arg(long, requires = "pomodoro", conflicts_with = "minutes", help = "Minutes of the break after the last work session [default: timer.long_break setting, 15]")]
+        long_break: Option<u64>,
+
+        #[arg(long, requires = "pomodoro", conflicts_with = "minutes", help = "Number of pomodoro work sessions [default: timer.cycles setting, 4]")]
+        cycles: Option<u64>,
+
+        #[arg(long = "todo", help = "Todo id to track the time against", add = ArgValueCandidates::new(open_todo_candidates))]
+        todo_id: Option<i32>,
+
+        #[arg(short, long, conflicts_with_all = ["pomodoro", "stopwatch"], help = "Run the timer in the background, an alert is shown when it elapses")]
+        background: bool,
+    },
+
+    #[command(name = "show", about = "Show todo details")]
+    Show {
+        #[arg(help = "Todo id to show", add = ArgValueCandidates::new(all_todo_candidates))]
+        id: i32,
+
+        #[arg(long, help = "Print the todo as JSON")]
+        json: bool,
+    },
+
+    #[command(name = "note", about = "Edit the notes of a todo in $EDITOR")]
+    Note {
+        #[arg(help = "Todo id whose notes to edit", add = ArgValueCandidates::new(open_todo_candidates))]
+        id: i32,
+    },
+
+    #[command(name = "edit", about = "Edit many todos at once in $EDITOR")]
+    Edit {
+        #[arg(help = "Only edit the todos matching the keyword")]
+        keyword: Vec<String>,
+
+        #[arg(short, long, help = "Include tasks marked as done")]
+        all: bool,
+    },
+
+    #[command(name = "stats", about = "Show how many todos were created and completed, and what is still open")]
+    Stats {
+        #[arg(short, long, default_value = "4 weeks ago", allow_hyphen_values = true, help = "Start of the period, e.g. yesterday, last fri, -2w or 2026-01-31")]
+        since: String,
+
+        #[arg(short, long, help = "Count per week instead of per day")]
+        week: bool,
+    },
+
+    #[command(name = "standup", about = "Print what was done and added since yesterday and what is open, in Markdown")]
+    Standup {
+        #[arg(short, long, default_value = "yesterday", allow_hyphen_values = true, help = "Start of the period, e.g. yesterday, last fri, -2d or 2026-01-31 09:00")]
+        since: String,
+
+        #[arg(short, long, default_value_t = 2, help = "Lowest priority of the open todos listed")]
+        priority: i32,
+    },
+
+    #[command(name = "review", about = "Go through the open todos not updated in a while, one at a time")]
+    Review {
+        #[arg(long, help = "Review todos not updated or reviewed in this many days, overrides review.days")]
+        days: Option<u64>,
+    },
+
+    #[command(name = "report", about = "Show reports")]
+    Report {
+        #[command(subcommand)]
+        report: Report,
+    },
+
+    #[command(name = "ui", about = "Start full-screen mode")]
+    Ui,
+
+    #[command(name = "import", about = "Import todos exported from another tool")]
+    Import {
+        #[arg(help = "File to import from, reads stdin when omitted")]
+        file: Option<String>,
+
+        #[arg(short, long, value_enum, default_value_t = Format::Taskwarrior, help = "Format of the imported todos")]
+        format: Format,
+    },
+
+    #[command(name = "export", about = "Export todos for another tool")]
+    Export {
+        #[arg(help = "File to export to, writes to stdout when omitted")]
+        file: Option<String>,
+
+        #[arg(short, long, value_enum, default_value_t = Format::Taskwarrior, help = "Format of the exported todos")]
+        format: Format,
+    },
+
+    #[command(name = "init", about = "Keep todos for the current directory and its subdirectories")]
+    Init {
+        #[arg(long, help = "Keep the todos in the global database under this project instead of in .tc.db")]
+        project: Option<String>,
+    },
+
+    #[command(name = "profile", about = "Manage profiles, each with a database of its own")]
+    Profile {
+        #[command(subcommand)]
+        action: ProfileAction,
+    },
+
+    #[command(name = "config", about = "Show and change settings")]
+    Config {
+        #[command(subcommand)]
+        action: ConfigAction,
+    },
+
+    #[command(name = "completions", about = "Print the shell completion script, e.g. source <(tc completions bash)")]
+    Completions {
+        #[arg(value_enum, help = "Shell to complete in")]
+        shell: Shell,
+    },
+
+    #[command(name = "man", about = "Print the man page, or write one for every subcommand")]
+    Man {
+        #[arg(long, help = "Directory to write tc.1 and the subcommand pages to")]
+        dir: Option<String>,
+    },
+}
+
+#[derive(Subcommand, PartialEq, Debug)]
+pub enum ProfileAction {
+    #[command(name = "ls", aliases = ["list"], about = "List profiles, the current one is marked with *")]
+    Ls,
+
+    #[command(name = "create", about = "Create a profile with an empty database")]
+    Create {
+        name: String,
+    },
+
+    #[command(name = "switch", about = "Use the profile from now on")]
+    Switch {
+        name: String,
+    },
+}
+
+#[derive(Subcommand, PartialEq, Debug)]
+pub enum ConfigAction {
+    #[command(name = "get", about = "Print the value of a setting")]
+    Get {
+        #[arg(help = "Setting name, e.g. timer.work")]
+        key: String,
+    },
+
+    #[command(name = "set", about = "Change a setting in the user config file")]
+    Set {
+        #[arg(help = "Setting name, e.g. timer.work")]
+        key: String,
+
+        #[arg(help = "New value, lists are comma separated")]
+        value: String,
+
+        #[arg(long, help = "Write to the project config file (.tc.toml) instead")]
+        local: bool,
+    },
+
+    #[command(name = "list", aliases = ["ls"], about = "List every setting with its value and where it comes from")]
+    List,
+
+    #[command(name = "path", about = "Show the config files in use")]
+    Path,
+}
+
+#[derive(Subcommand, PartialEq, Debug)]
+pub enum Report {
+    #[command(name = "time", about = "Show time tracked against todos, by day")]
+    Time {
+        #[arg(short, long, help = "Report the current week instead of today")]
+        week: bool,
     },
 }
+
+#[derive(Subcommand, PartialEq, Debug)]
+pub enum TimerAction {
+    #[command(name = "status", about = "Show the remaining time of background timers")]
+    Status,
+
+    #[command(name = "cancel", about = "Cancel background timers")]
+    Cancel {
+        #[arg(help = "Timer id to cancel, cancels every running timer when omitted")]
+        id: Option<i32>,
+    },
+
+    // Started by `tc t --background`, waits for the timer in a detached process
+    #[command(name = "watch", hide = true)]
+    Watch {
+        id: i32,
+    },
+}
+
+#[derive(ValueEnum, Clone, Copy, PartialEq, Debug)]
+pub enum Format {
+    #[value(alias = "tw", help = "JSON produced by `task export` and accepted by `task import`")]
+    Taskwarrior,
+
+    #[value(help = "Org-mode headlines, subtasks are nested under their parent")]
+    Org,
+}
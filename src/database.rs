@@ -1,22 +1,66 @@
 use std::fs;
+use std::path::Path;
 
-use rusqlite::{Connection, params, Result};
+use chrono::NaiveDateTime;
+use rusqlite::types::ToSqlOutput;
+use rusqlite::{Connection, OptionalExtension, params, params_from_iter, Result, Row, ToSql, TransactionBehavior};
 
+use crate::dates;
+use crate::settings::DuplicatePolicy;
 use crate::utils::log;
 
+const SESSION_COLUMNS: &str = "s.todo_id, t.title, s.started_at, s.duration, s.completed";
+
+// user_version of databases whose timestamps are stored in UTC
+const TIMESTAMPS_IN_UTC: i32 = 1;
+
+pub const TIMER_RUNNING: &str = "running";
+pub const TIMER_FIRED: &str = "fired";
+pub const TIMER_CANCELLED: &str = "cancelled";
+
+const TODO_COLUMNS: &str = "id, title, done, priority, created_at, updated_at, due_at, project, tags, uuid, scheduled_at, parent_id, context, estimate, notes";
+
+// Columns added after the first release, with the definition used to add them to existing databases
+const TODO_COLUMN_MIGRATIONS: [(&str, &str); 10] = [
+    ("due_at", "DATE"),
+    ("project", "TEXT"),
+    ("tags", "TEXT NOT NULL DEFAULT ''"),
+    ("uuid", "TEXT"),
+    ("scheduled_at", "DATE"),
+    ("parent_id", "INTEGER"),
+    ("context", "TEXT"),
+    ("estimate", "INTEGER"),
+    ("notes", "TEXT NOT NULL DEFAULT ''"),
+    ("reviewed_at", "DATE"),
+];
+
 pub struct TodoDatabase {
     db_dir_path: String,
     db_name: String,
     db_todo_table_ddl: String,
     db_todo_index_ddl: String,
+    db_session_table_ddl: String,
+    db_timer_table_ddl: String,
+    db_transition_table_ddl: String,
+    // Project the todos are limited to, set inside a directory with a `.tc` marker
+    project: Option<String>,
 }
 
 impl TodoDatabase {
-    pub fn new() -> TodoDatabase {
-        Self::new0(
-            format!("{}/.tc", std::env::var("HOME").unwrap()),
-            "todo.db".to_string(),
-        )
+    pub fn with_path(path: &str) -> TodoDatabase {
+        let path = Path::new(path);
+        let db_dir_path = match path.parent() {
+            Some(dir) if !dir.as_os_str().is_empty() => dir.to_string_lossy().to_string(),
+            _ => ".".to_string(),
+        };
+        let db_name = path.file_name().map_or("todo.db".to_string(), |name| name.to_string_lossy().to_string());
+        Self::new0(db_dir_path, db_name)
+    }
+
+    // Limits listing, finding, adding and removing todos to the project
+    pub fn with_project(mut self, project: &str) -> TodoDatabase {
+        self.project = Some(project.to_string());
+        self
     }
 
     fn new0(
@@ -34,12 +78,56 @@ impl TodoDatabase {
                 done       BOOLEAN         NOT NULL, -- whether the todo is done or not
                 priority   INTEGER         NOT NULL DEFAULT 0, -- priority of the todo
                 created_at DATE            NOT NULL,
-                updated_at DATE            NOT NULL
+                updated_at DATE            NOT NULL,
+                due_at     DATE,                     -- when the todo is due
+                project    TEXT,                     -- project the todo belongs to
+                tags       TEXT            NOT NULL DEFAULT '', -- space separated tags
+                uuid       TEXT,                     -- stable id shared with other todo tools
+                scheduled_at DATE,                   -- when work on the todo is planned to start
+                parent_id  INTEGER,                  -- id of the todo this todo is a subtask of
+                context    TEXT,                     -- where the todo can be done, e.g. home or phone
+                estimate   INTEGER,                  -- expected seconds of work
+                notes      TEXT            NOT NULL DEFAULT '', -- long-form description
+                reviewed_at DATE                     -- when the todo was last looked at by `tc review`
             )
             "#.to_string(),
             db_todo_index_ddl: r#"
-            CREATE INDEX IF NOT EXISTS idx_todos_done ON todos (done)
+            CREATE INDEX IF NOT EXISTS idx_todos_done ON todos (done);
+            CREATE UNIQUE INDEX IF NOT EXISTS idx_todos_uuid ON todos (uuid);
+            "#.to_string(),
+            db_session_table_ddl: r#"
+            CREATE TABLE IF NOT EXISTS sessions
+            (
+                id         INTEGER PRIMARY KEY,
+                todo_id    INTEGER         NOT NULL, -- todo the time was spent on
+                started_at DATE            NOT NULL,
+                ended_at   DATE            NOT NULL,
+                duration   INTEGER         NOT NULL, -- seconds actually spent, excluding pauses
+                completed  BOOLEAN         NOT NULL  -- false when the timer was interrupted
+            );
+            CREATE INDEX IF NOT EXISTS idx_sessions_todo_id ON sessions (todo_id);
+            "#.to_string(),
+            db_timer_table_ddl: r#"
+            CREATE TABLE IF NOT EXISTS timers
+            (
+                id         INTEGER PRIMARY KEY,
+                todo_id    INTEGER,                  -- todo the time is tracked against, if any
+                started_at DATE            NOT NULL,
+                ends_at    DATE            NOT NULL,
+                status     TEXT            NOT NULL  -- running, fired or cancelled
+            )
             "#.to_string(),
+            db_transition_table_ddl: r#"
+            CREATE TABLE IF NOT EXISTS transitions
+            (
+                id         INTEGER PRIMARY KEY,
+                todo_id    INTEGER         NOT NULL, -- todo that was marked as done or undone
+                done       BOOLEAN         NOT NULL, -- the state the todo changed to
+                at         DATE            NOT NULL
+            );
+            CREATE INDEX IF NOT EXISTS idx_transitions_todo_id ON transitions (todo_id);
+            "#.to_string(),
+            project: None,
         }
     }
 
@@ -48,25 +136,81 @@ impl TodoDatabase {
     }
 
     pub fn initialize(&self) -> Result<()> {
-        if self.is_initialized() {
-            return Ok(());
+        if !self.is_initialized() {
+            match fs::create_dir_all(&self.db_dir_path) {
+                Ok(_) => {
+                    log(&format!("Created directory: {}", self.db_dir_path));
+                }
+                Err(_) => {
+                    panic!("Failed to create directory: {}, stopping todo-cli", self.db_dir_path);
+                }
+            }
         }
 
-        match fs::create_dir_all(&self.db_dir_path) {
-            Ok(_) => {
-                log(&format!("Created directory: {}", self.db_dir_path));
-            }
-            Err(_) => {
-                panic!("Failed to create directory: {}, stopping todo-cli", self.db_dir_path);
+        let mut conn = Connection::open(self.get_db_path())?;
+
+        conn.execute(&self.db_todo_table_ddl, [])?;
+        Self::migrate(&conn)?;
+        conn.execute_batch(&self.db_todo_index_ddl)?;
+        conn.execute_batch(&self.db_session_table_ddl)?;
+        conn.execute(&self.db_timer_table_ddl, [])?;
+        conn.execute_batch(&self.db_transition_table_ddl)?;
+        Self::migrate_timestamps(&mut conn)?;
+
+        Ok(())
+    }
+
+    // Databases created by older versions lack the newer columns, add them in place
+    fn migrate(conn: &Connection) -> Result<()> {
+        let columns = conn
+            .prepare("SELECT name FROM pragma_table_info('todos')")?
+            .query_map([], |row| row.get::<_, String>(0))?
+            .collect::<Result<Vec<String>>>()?;
+
+        for (column, definition) in TODO_COLUMN_MIGRATIONS {
+            if !columns.iter().any(|c| c == column) {
+                conn.execute(&format!("ALTER TABLE todos ADD COLUMN {} {}", column, definition), [])?;
             }
         }
 
-        let conn = Connection::open(self.get_db_path())?;
+        Ok(())
+    }
 
-        conn.execute(&*self.db_todo_table_ddl, [])?;
-        conn.execute(&*self.db_todo_index_ddl, [])?;
+    // Older versions stored timestamps in local time with an offset, which sort and compare wrongly once the offset
+    // changes with DST or a move. Rewrites them once in UTC, user_version records that it was done.
+    // Everything runs in one transaction, so an interrupted migration is run again from the start.
+    fn migrate_timestamps(conn: &mut Connection) -> Result<()> {
+        let tx = conn.transaction()?;
+        let version: i32 = tx.query_row("PRAGMA user_version", [], |row| row.get(0))?;
+        if version >= TIMESTAMPS_IN_UTC {
+            return Ok(());
+        }
 
-        Ok(())
+        let columns = [
+            ("todos", "created_at"),
+            ("todos", "updated_at"),
+            ("todos", "due_at"),
+            ("todos", "scheduled_at"),
+            ("sessions", "started_at"),
+            ("sessions", "ended_at"),
+            ("timers", "started_at"),
+            ("timers", "ends_at"),
+        ];
+        // SQLite applies the offset of the timestamp when formatting it, which gives the UTC time
+        for (table, column) in columns {
+            tx.execute(
+                &format!(
+                    "UPDATE {table} SET {column} = strftime('{format}', {column}) WHERE {column} NOT LIKE '%Z' AND strftime('{format}', {column}) IS NOT NULL",
+                    table = table,
+                    column = column,
+                    format = "%Y-%m-%dT%H:%M:%SZ",
+                ),
+                [],
+            )?;
+        }
+        tx.execute(&format!("PRAGMA user_version = {}", TIMESTAMPS_IN_UTC), [])?;
+
+        tx.commit()
     }
 
     #[allow(dead_code)]
@@ -91,57 +235,142 @@ impl TodoDatabase {
         fs::metadata(db_path).is_ok()
     }
 
-    pub fn add_todo(&self, todo: &str, priority: Option<i32>) -> Result<()> {
+    // Adds the todo, or applies the policy when open todos share its title
+    pub fn add_new_todo(&self, todo: &NewTodo, policy: DuplicatePolicy) -> Result<Added> {
+        let mut conn = Connection::open(self.get_db_path())?;
+        let tx = conn.transaction()?;
+        let now = dates::now_timestamp();
+
+        let duplicates = tx
+            .prepare("SELECT id, priority, tags FROM todos WHERE title = ?1 AND done = 0 AND (?2 IS NULL OR project = ?2) ORDER BY id")?
+            .query_map(params![todo.title, self.project], |row| Ok((row.get::<_, i32>(0)?, row.get::<_, i32>(1)?, row.get::<_, String>(2)?)))?
+            .collect::<Result<Vec<_>>>()?;
+
+        let added = match (policy, duplicates.first()) {
+            (DuplicatePolicy::Reject, Some((id, _, _))) => Added::Rejected { id: *id },
+            (DuplicatePolicy::MergeAndBumpPriority, Some((id, priority, tags))) => {
+                let priority = (priority + 1).max(todo.priority.unwrap_or(0));
+                let mut tags: Vec<&str> = tags.split_whitespace().collect();
+                for tag in &todo.tags {
+                    if !tags.contains(&tag.as_str()) {
+                        tags.push(tag);
+                    }
+                }
+                tx.execute(
+                    "UPDATE todos SET priority = ?1, tags = ?2, due_at = COALESCE(?3, due_at), context = COALESCE(?4, context), estimate = COALESCE(?5, estimate), updated_at = ?6 WHERE id = ?7",
+                    params![priority, tags.join(" "), todo.due_at, todo.context, todo.estimate, now, id],
+                )?;
+                Added::Merged { id: *id, priority }
+            }
+            (policy, _) => {
+                let mut completed = Vec::new();
+                if policy == DuplicatePolicy::CompletePrevious {
+                    for (id, _, _) in &duplicates {
+                        record_transitions(&tx, &[*id], true, &now)?;
+                        tx.execute("UPDATE todos SET done = ?1, updated_at = ?2 WHERE id = ?3", params![true, now, id])?;
+                        completed.push(*id);
+                    }
+                }
+
+                tx.execute(
+                    "INSERT INTO todos (title, done, priority, created_at, updated_at, project, due_at, tags, context, estimate) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+                    params![todo.title, false, todo.priority.unwrap_or(0), now, now, self.project, todo.due_at, todo.tags.join(" "), todo.context, todo.estimate],
+                )?;
+                Added::New { id: tx.last_insert_rowid() as i32, completed }
+            }
+        };
+
+        tx.commit()?;
+        Ok(added)
+    }
+
+    // Returns false when there is no todo with the id in the project
+    pub fn update_todo(&self, id: i32, todo: &str) -> Result<bool> {
         let conn = Connection::open(self.get_db_path())?;
-        let now = chrono::Local::now().to_rfc3339();
+        let now = dates::now_timestamp();
 
-        // Mark todo with same name as done
-        conn.execute(
-            "UPDATE todos SET done = ?1, updated_at = ?2 WHERE title = ?3",
-            params![true, now, todo],
+        let updated = conn.execute(
+            "UPDATE todos SET title = ?1, updated_at = ?2 WHERE id = ?3 AND (?4 IS NULL OR project = ?4)",
+            params![todo, now, id, self.project],
         )?;
 
-        conn.execute(
-            "INSERT INTO todos (title, done, priority, created_at, updated_at) VALUES (?1, ?2, ?3, ?4, ?5)",
-            params![todo, false, priority.unwrap_or(0), now, now],
+        Ok(updated == 1)
+    }
+
+    // Returns false when there is no todo with the id in the project
+    pub fn set_notes(&self, id: i32, notes: &str) -> Result<bool> {
+        let conn = Connection::open(self.get_db_path())?;
+        let now = dates::now_timestamp();
+
+        let updated = conn.execute(
+            "UPDATE todos SET notes = ?1, updated_at = ?2 WHERE id = ?3 AND (?4 IS NULL OR project = ?4)",
+            params![notes, now, id, self.project],
         )?;
 
-        Ok(())
+        Ok(updated == 1)
     }
 
-    pub fn update_todo(&self, id: i32, todo: &str) -> Result<()> {
+    // Returns false when there is no todo with the id in the project
+    pub fn set_due(&self, id: i32, due_at: Option<&str>) -> Result<bool> {
         let conn = Connection::open(self.get_db_path())?;
-        let now = chrono::Local::now().to_rfc3339();
+        let now = dates::now_timestamp();
 
-        conn.execute(
-            "UPDATE todos SET title = ?1, updated_at = ?2 WHERE id = ?3",
-            params![todo, now, id],
+        let updated = conn.execute(
+            "UPDATE todos SET due_at = ?1, updated_at = ?2 WHERE id = ?3 AND (?4 IS NULL OR project = ?4)",
+            params![due_at, now, id, self.project],
         )?;
 
+        Ok(updated == 1)
+    }
+
+    // Reviewing isn't a change to the todo, updated_at is kept
+    pub fn mark_reviewed(&self, id: i32) -> Result<()> {
+        let conn = Connection::open(self.get_db_path())?;
+        conn.execute(
+            "UPDATE todos SET reviewed_at = ?1 WHERE id = ?2 AND (?3 IS NULL OR project = ?3)",
+            params![dates::now_timestamp(), id, self.project],
+        )?;
         Ok(())
     }
 
+    // Open todos neither updated nor reviewed since `since`, the oldest first
+    pub fn list_stale_todos(&self, since: &str) -> Result<Vec<Todo>> {
+        let conn = Connection::open(self.get_db_path())?;
+        let sql = format!(
+            "SELECT {} FROM todos WHERE done = 0 AND updated_at < ?1 AND (reviewed_at IS NULL OR reviewed_at < ?1) AND (?2 IS NULL OR project = ?2) ORDER BY updated_at, id",
+            TODO_COLUMNS
+        );
+        let mut stmt = conn.prepare(&sql)?;
+        let todos = stmt.query_map(params![since, self.project], Todo::from_row)?;
+        todos.collect()
+    }
+
+    // Returns false when there is no todo with the id in the project
+    pub fn update_priority(&self, id: i32, priority: i32) -> Result<bool> {
+        let conn = Connection::open(self.get_db_path())?;
+        let now = dates::now_timestamp();
+
+        let updated = conn.execute(
+            "UPDATE todos SET priority = ?1, updated_at = ?2 WHERE id = ?3 AND (?4 IS NULL OR project = ?4)",
+            params![priority, now, id, self.project],
+        )?;
+
+        Ok(updated == 1)
+    }
+
+    pub fn get_todo(&self, id: i32) -> Result<Option<Todo>> {
+        let conn = Connection::open(self.get_db_path())?;
+        let sql = format!("SELECT {} FROM todos WHERE id = ?1 AND (?2 IS NULL OR project = ?2)", TODO_COLUMNS);
+        conn.query_row(sql.as_str(), params![id, self.project], Todo::from_row).optional()
+    }
+
     pub fn list_todos(&self, include_all: bool) -> Result<Vec<Todo>> {
         let conn = Connection::open(self.get_db_path())?;
-        let sql = if include_all {
-            "SELECT id, title, done, priority, created_at, updated_at FROM todos"
-        } else {
-            "SELECT id, title, done, priority, created_at, updated_at FROM todos WHERE done = ?1"
-        };
-        let mut stmt = conn.prepare(sql)?;
+        let sql = format!("SELECT {} FROM todos WHERE (?1 OR done = 0) AND (?2 IS NULL OR project = ?2)", TODO_COLUMNS);
+        let mut stmt = conn.prepare(sql.as_str())?;
 
-        let params = if include_all { params![] } else { params![false] };
         let todos = stmt
-            .query_map(params, |row| {
-                Ok(Todo {
-                    id: row.get(0)?,
-                    title: row.get(1)?,
-                    done: row.get(2)?,
-                    priority: row.get(3)?,
-                    created_at: row.get(4)?,
-                    updated_at: row.get(5)?,
-                })
-            })?
+            .query_map(params![include_all, self.project], Todo::from_row)?
             .map(|r| r.unwrap())
             .collect();
 
@@ -150,84 +379,318 @@ impl TodoDatabase {
 
     pub fn find_todos(&self, keyword: &str, include_all: bool) -> Result<Vec<Todo>> {
         let conn = Connection::open(self.get_db_path())?;
-        let sql = if include_all {
-            format!("SELECT id, title, done, priority, created_at, updated_at FROM todos WHERE title LIKE '%{}%'", keyword)
-        } else {
-            format!("SELECT id, title, done, priority, created_at, updated_at FROM todos WHERE title LIKE '%{}%' AND done = ?1", keyword)
-        };
+        let sql = format!(
+            "SELECT {} FROM todos WHERE (title LIKE '%' || ?1 || '%' ESCAPE '\\' OR notes LIKE '%' || ?1 || '%' ESCAPE '\\') AND (?2 OR done = 0) AND (?3 IS NULL OR project = ?3)",
+            TODO_COLUMNS
+        );
         let mut stmt = conn.prepare(sql.as_str())?;
 
-        let params = if include_all { params![] } else { params![false] };
+        // `%` and `_` in the keyword match themselves, not any text or character
+        let keyword = keyword.replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_");
         let todos = stmt
-            .query_map(params, |row| {
-                Ok(Todo {
-                    id: row.get(0)?,
-                    title: row.get(1)?,
-                    done: row.get(2)?,
-                    priority: row.get(3)?,
-                    created_at: row.get(4)?,
-                    updated_at: row.get(5)?,
-                })
-            })?
+            .query_map(params![keyword, include_all, self.project], Todo::from_row)?
             .map(|r| r.unwrap())
             .collect();
 
         Ok(todos)
     }
 
-    pub fn mark_as_done(&self, ids: &Vec<i32>) -> Result<()> {
+    // Returns the ids which aren't todos of the project, they are left alone
+    pub fn mark_as_done(&self, ids: &[i32]) -> Result<Vec<i32>> {
+        self.set_done(ids, true)
+    }
+
+    // Returns the ids which aren't todos of the project, they are left alone
+    pub fn mark_as_undone(&self, ids: &[i32]) -> Result<Vec<i32>> {
+        self.set_done(ids, false)
+    }
+
+    fn set_done(&self, ids: &[i32], done: bool) -> Result<Vec<i32>> {
+        let mut conn = Connection::open(self.get_db_path())?;
+        let tx = conn.transaction()?;
+        let now = dates::now_timestamp();
+
+        let found = scoped_ids(&tx, ids, &self.project)?;
+        if !found.is_empty() {
+            record_transitions(&tx, &found, done, &now)?;
+            let sql = format!("UPDATE todos SET done = ?, updated_at = ? WHERE id IN ({})", placeholders(found.len()));
+            let values = [done.to_sql()?, now.to_sql()?].into_iter().chain(found.iter().map(|id| ToSqlOutput::from(*id)));
+            tx.execute(&sql, params_from_iter(values))?;
+        }
+
+        tx.commit()?;
+        Ok(ids.iter().filter(|id| !found.contains(id)).copied().collect())
+    }
+
+    // Returns the ids which aren't todos of the project, they are left alone
+    pub fn remove_todos_by_ids(&self, ids: &[i32]) -> Result<Vec<i32>> {
+        let mut conn = Connection::open(self.get_db_path())?;
+        let tx = conn.transaction()?;
+
+        let found = scoped_ids(&tx, ids, &self.project)?;
+        remove_todos(&tx, &found)?;
+
+        tx.commit()?;
+        Ok(ids.iter().filter(|id| !found.contains(id)).copied().collect())
+    }
+
+    // Applies the changes of `tc edit` all at once, added todos go to the current project. The updated and removed
+    // todos carry the updated_at they were edited with, when any of them changed or was removed since, nothing is
+    // applied and their ids are returned.
+    pub fn apply_edits(&self, updated: &[Todo], added: &[Todo], removed: &[Todo]) -> Result<Vec<i32>> {
+        let mut conn = Connection::open(self.get_db_path())?;
+        // Takes the write lock up front, so nothing changes between the check and the edits
+        let tx = conn.transaction_with_behavior(TransactionBehavior::Immediate)?;
+        let now = dates::now_timestamp();
+
+        let mut changed = Vec::new();
+        for todo in updated.iter().chain(removed) {
+            let updated_at: Option<String> = tx
+                .query_row(
+                    "SELECT updated_at FROM todos WHERE id = ?1 AND (?2 IS NULL OR project = ?2)",
+                    params![todo.id, self.project],
+                    |row| row.get(0),
+                )
+                .optional()?;
+            if updated_at.as_deref() != Some(todo.updated_at.as_str()) {
+                changed.push(todo.id);
+            }
+        }
+        if !changed.is_empty() {
+            return Ok(changed);
+        }
+
+        for todo in updated {
+            record_transitions(&tx, &[todo.id], todo.done, &now)?;
+            tx.execute(
+                "UPDATE todos SET title = ?1, priority = ?2, done = ?3, updated_at = ?4 WHERE id = ?5",
+                params![todo.title, todo.priority, todo.done, now, todo.id],
+            )?;
+        }
+        for todo in added {
+            tx.execute(
+                "INSERT INTO todos (title, done, priority, created_at, updated_at, project) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                params![todo.title, todo.done, todo.priority, now, now, self.project],
+            )?;
+            if todo.done {
+                tx.execute("INSERT INTO transitions (todo_id, done, at) VALUES (?1, ?2, ?3)", params![tx.last_insert_rowid(), true, now])?;
+            }
+        }
+        remove_todos(&tx, &removed.iter().map(|todo| todo.id).collect::<Vec<i32>>())?;
+
+        tx.commit()?;
+        Ok(changed)
+    }
+
+    // Removes the todos created up to and including `until`, local time
+    pub fn remove_todos_until_date(&self, until: NaiveDateTime) -> Result<()> {
+        let mut conn = Connection::open(self.get_db_path())?;
+        let tx = conn.transaction()?;
+        let until = dates::to_timestamp(until);
+
+        // Removed ids are reused by the next todos, which must not inherit the tracked time and timers
+        for table in ["sessions", "timers", "transitions"] {
+            tx.execute(
+                &format!("DELETE FROM {} WHERE todo_id IN (SELECT id FROM todos WHERE created_at <= ?1 AND (?2 IS NULL OR project = ?2))", table),
+                params![until, self.project],
+            )?;
+        }
+        tx.execute("DELETE FROM todos WHERE created_at <= ?1 AND (?2 IS NULL OR project = ?2)", params![until, self.project])?;
+
+        tx.commit()
+    }
+
+    // Imports every todo or none of them. Each todo is paired with the position of its parent in `entries`, which
+    // has to come before it. Returns the ids of the imported todos.
+    pub fn import_todos(&self, entries: &[(Todo, Option<usize>)]) -> Result<Vec<i32>> {
+        let mut conn = Connection::open(self.get_db_path())?;
+        let tx = conn.transaction()?;
+
+        let mut ids: Vec<i32> = Vec::with_capacity(entries.len());
+        for (todo, parent) in entries {
+            let parent_id = parent.map(|index| ids[index]);
+            ids.push(import_todo(&tx, todo, parent_id, &self.project)?);
+        }
+
+        tx.commit()?;
+        Ok(ids)
+    }
+
+    pub fn set_uuid(&self, id: i32, uuid: &str) -> Result<()> {
         let conn = Connection::open(self.get_db_path())?;
-        let now = chrono::Local::now().to_rfc3339();
+        conn.execute("UPDATE todos SET uuid = ?1 WHERE id = ?2", params![uuid, id])?;
+        Ok(())
+    }
 
-        let ids_str = ids.iter().map(|id| id.to_string()).collect::<Vec<String>>().join(",");
+    pub fn add_session(&self, todo_id: i32, started_at: &str, ended_at: &str, duration: i64, completed: bool) -> Result<()> {
+        let conn = Connection::open(self.get_db_path())?;
         conn.execute(
-            "UPDATE todos SET done = ?1, updated_at = ?2 WHERE id IN (?3)",
-            params![true, now, ids_str],
+            "INSERT INTO sessions (todo_id, started_at, ended_at, duration, completed) VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![todo_id, started_at, ended_at, duration, completed],
         )?;
-
         Ok(())
     }
 
-    pub fn mark_as_undone(&self, ids: &Vec<i32>) -> Result<()> {
+    pub fn list_sessions(&self, todo_id: i32) -> Result<Vec<Session>> {
         let conn = Connection::open(self.get_db_path())?;
-        let now = chrono::Local::now().to_rfc3339();
+        let sql = format!(
+            "SELECT {} FROM sessions s JOIN todos t ON t.id = s.todo_id WHERE s.todo_id = ?1 AND (?2 IS NULL OR t.project = ?2) ORDER BY s.started_at",
+            SESSION_COLUMNS
+        );
+        let mut stmt = conn.prepare(sql.as_str())?;
+        let sessions = stmt.query_map(params![todo_id, self.project], Session::from_row)?.collect();
+        sessions
+    }
+
+    // Sessions of the project's todos started within [from, until), along with the title of their todo
+    pub fn list_sessions_between(&self, from: &str, until: &str) -> Result<Vec<Session>> {
+        let conn = Connection::open(self.get_db_path())?;
+        let sql = format!(
+            "SELECT {} FROM sessions s JOIN todos t ON t.id = s.todo_id WHERE s.started_at >= ?1 AND s.started_at < ?2 AND (?3 IS NULL OR t.project = ?3) ORDER BY s.started_at",
+            SESSION_COLUMNS
+        );
+        let mut stmt = conn.prepare(sql.as_str())?;
+        let sessions = stmt.query_map(params![from, until, self.project], Session::from_row)?.collect();
+        sessions
+    }
 
-        let ids_str = ids.iter().map(|id| id.to_string()).collect::<Vec<String>>().join(",");
+    pub fn add_timer(&self, todo_id: Option<i32>, started_at: &str, ends_at: &str) -> Result<i32> {
+        let conn = Connection::open(self.get_db_path())?;
         conn.execute(
-            "UPDATE todos SET done = ?1, updated_at = ?2 WHERE id IN (?3)",
-            params![false, now, ids_str],
+            "INSERT INTO timers (todo_id, started_at, ends_at, status) VALUES (?1, ?2, ?3, ?4)",
+            params![todo_id, started_at, ends_at, TIMER_RUNNING],
         )?;
-
-        Ok(())
+        Ok(conn.last_insert_rowid() as i32)
     }
 
-    pub fn remove_todos_by_ids(&self, ids: &Vec<i32>) -> Result<()> {
+    pub fn get_timer(&self, id: i32) -> Result<Option<BackgroundTimer>> {
         let conn = Connection::open(self.get_db_path())?;
+        conn.query_row(
+            "SELECT id, todo_id, started_at, ends_at, status FROM timers WHERE id = ?1",
+            params![id],
+            BackgroundTimer::from_row,
+        ).optional()
+    }
 
-        let ids_str = ids.iter().map(|id| id.to_string()).collect::<Vec<String>>().join(",");
-        conn.execute("DELETE FROM todos WHERE id IN (?1)", params![ids_str])?;
+    // Running timers of the project's todos, and the timers without a todo which belong to no project
+    pub fn list_running_timers(&self) -> Result<Vec<BackgroundTimer>> {
+        let conn = Connection::open(self.get_db_path())?;
+        let mut stmt = conn.prepare(
+            "SELECT m.id, m.todo_id, m.started_at, m.ends_at, m.status FROM timers m LEFT JOIN todos t ON t.id = m.todo_id \
+             WHERE m.status = ?1 AND (?2 IS NULL OR m.todo_id IS NULL OR t.project = ?2) ORDER BY m.ends_at",
+        )?;
+        let timers = stmt.query_map(params![TIMER_RUNNING, self.project], BackgroundTimer::from_row)?.collect();
+        timers
+    }
 
-        Ok(())
+    // Moves a running timer to the given status. Returns false when the timer wasn't running anymore,
+    // which lets concurrent tc processes agree on which one fires the alert
+    pub fn finish_timer(&self, id: i32, status: &str) -> Result<bool> {
+        let conn = Connection::open(self.get_db_path())?;
+        let updated = conn.execute(
+            "UPDATE timers SET status = ?1 WHERE id = ?2 AND status = ?3",
+            params![status, id, TIMER_RUNNING],
+        )?;
+        Ok(updated == 1)
     }
 
-    pub fn remove_todos_until_date(&self, date: &str) -> Result<()> {
+    // The done and undone transitions of the todo, oldest first
+    pub fn list_transitions(&self, todo_id: i32) -> Result<Vec<Transition>> {
         let conn = Connection::open(self.get_db_path())?;
-        let parsed_date = chrono::NaiveDate::parse_from_str(date, "%Y-%m-%d")
-            .map_err(|_| rusqlite::Error::InvalidParameterName("Invalid date format".to_string()))?;
-        let end_of_day = parsed_date.and_hms_opt(23, 59, 59)
-            .ok_or_else(|| rusqlite::Error::InvalidParameterName("Invalid date format".to_string()))?;
-        let end_of_day_str = end_of_day.format("%Y-%m-%dT%H:%M:%S").to_string();
-        conn.execute("DELETE FROM todos WHERE created_at <= ?1", params![end_of_day_str])?;
-        Ok(())
+        let mut stmt = conn.prepare("SELECT done, at FROM transitions WHERE todo_id = ?1 ORDER BY at, id")?;
+        let transitions = stmt.query_map(params![todo_id], |row| Ok(Transition { done: row.get(0)?, at: row.get(1)? }))?;
+        transitions.collect()
     }
 
     pub fn reset(&self) -> Result<()> {
-        let conn = Connection::open(self.get_db_path())?;
-        conn.execute("DELETE FROM todos", [])?;
-        Ok(())
+        let mut conn = Connection::open(self.get_db_path())?;
+        let tx = conn.transaction()?;
+
+        for table in ["sessions", "timers", "transitions"] {
+            tx.execute(
+                &format!("DELETE FROM {} WHERE todo_id IN (SELECT id FROM todos WHERE ?1 IS NULL OR project = ?1)", table),
+                params![self.project],
+            )?;
+        }
+        tx.execute("DELETE FROM todos WHERE ?1 IS NULL OR project = ?1", params![self.project])?;
+
+        tx.commit()
     }
 }
 
+// Records the todos whose done state is about to change to `done`, call it before updating them
+fn record_transitions(conn: &Connection, ids: &[i32], done: bool, at: &str) -> Result<()> {
+    if ids.is_empty() {
+        return Ok(());
+    }
+    let sql = format!("INSERT INTO transitions (todo_id, done, at) SELECT id, ?, ? FROM todos WHERE done != ? AND id IN ({})", placeholders(ids.len()));
+    let values = [done.to_sql()?, at.to_sql()?, done.to_sql()?].into_iter().chain(ids.iter().map(|id| ToSqlOutput::from(*id)));
+    conn.execute(&sql, params_from_iter(values))?;
+    Ok(())
+}
+
+// Inserts the todo as is, or overwrites the existing todo sharing its uuid. With a project scope the todo lands in
+// that project, and a todo of another project is never overwritten. Returns the id of the todo
+fn import_todo(conn: &Connection, todo: &Todo, parent_id: Option<i32>, project: &Option<String>) -> Result<i32> {
+    let tags = todo.tags.join(" ");
+    let todo_project = project.as_ref().or(todo.project.as_ref());
+
+    if let Some(uuid) = &todo.uuid {
+        let existing_id = conn
+            .query_row(
+                "SELECT id FROM todos WHERE uuid = ?1 AND (?2 IS NULL OR project = ?2)",
+                params![uuid, project],
+                |row| row.get::<_, i32>(0),
+            )
+            .optional()?;
+        if let Some(id) = existing_id {
+            record_transitions(conn, &[id], todo.done, &todo.updated_at)?;
+            conn.execute(
+                "UPDATE todos SET title = ?1, done = ?2, priority = ?3, created_at = ?4, updated_at = ?5, due_at = ?6, project = ?7, tags = ?8, scheduled_at = ?9, parent_id = ?10 WHERE id = ?11",
+                params![todo.title, todo.done, todo.priority, todo.created_at, todo.updated_at, todo.due_at, todo_project, tags, todo.scheduled_at, parent_id, id],
+            )?;
+            return Ok(id);
+        }
+    }
+
+    conn.execute(
+        "INSERT INTO todos (title, done, priority, created_at, updated_at, due_at, project, tags, uuid, scheduled_at, parent_id) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
+        params![todo.title, todo.done, todo.priority, todo.created_at, todo.updated_at, todo.due_at, todo_project, tags, todo.uuid, todo.scheduled_at, parent_id],
+    )?;
+
+    Ok(conn.last_insert_rowid() as i32)
+}
+
+// Removed ids are reused by the next todos, which must not inherit the tracked time and timers
+fn remove_todos(conn: &Connection, ids: &[i32]) -> Result<()> {
+    if ids.is_empty() {
+        return Ok(());
+    }
+    for table in ["sessions", "timers", "transitions"] {
+        conn.execute(&format!("DELETE FROM {} WHERE todo_id IN ({})", table, placeholders(ids.len())), params_from_iter(ids))?;
+    }
+    conn.execute(&format!("DELETE FROM todos WHERE id IN ({})", placeholders(ids.len())), params_from_iter(ids))?;
+    Ok(())
+}
+
+// The ids of todos in the project, every existing id without a project
+fn scoped_ids(conn: &Connection, ids: &[i32], project: &Option<String>) -> Result<Vec<i32>> {
+    if ids.is_empty() {
+        return Ok(Vec::new());
+    }
+    // The unnumbered placeholders are numbered after ?1
+    let sql = format!("SELECT id FROM todos WHERE (?1 IS NULL OR project = ?1) AND id IN ({})", placeholders(ids.len()));
+    let values = [project.to_sql()?].into_iter().chain(ids.iter().map(|id| ToSqlOutput::from(*id)));
+    let mut stmt = conn.prepare(&sql)?;
+    let found = stmt.query_map(params_from_iter(values), |row| row.get(0))?;
+    found.collect()
+}
+
+// `?, ?, ?` binding one id each, a single parameter would bind the ids as one text
+fn placeholders(count: usize) -> String {
+    vec!["?"; count].join(", ")
+}
+
 #[derive(Clone, Debug)]
 pub struct Todo {
     pub(crate) id: i32,
@@ -235,10 +698,129 @@ pub struct Todo {
     pub(crate) done: bool,
     pub(crate) priority: i32,
     pub(crate) created_at: String,
-    #[allow(dead_code)]
-    updated_at: String,
+    pub(crate) updated_at: String,
+    pub(crate) due_at: Option<String>,
+    pub(crate) project: Option<String>,
+    pub(crate) tags: Vec<String>,
+    pub(crate) uuid: Option<String>,
+    pub(crate) scheduled_at: Option<String>,
+    pub(crate) parent_id: Option<i32>,
+    pub(crate) context: Option<String>,
+    pub(crate) estimate: Option<i64>,
+    pub(crate) notes: String,
+}
+
+impl Todo {
+    // An open todo created now, the other fields are set with the struct update syntax
+    pub fn new(id: i32, title: &str) -> Todo {
+        let now = dates::now_timestamp();
+        Todo {
+            id,
+            title: title.to_string(),
+            done: false,
+            priority: 0,
+            created_at: now.clone(),
+            updated_at: now,
+            due_at: None,
+            project: None,
+            tags: Vec::new(),
+            uuid: None,
+            scheduled_at: None,
+            parent_id: None,
+            context: None,
+            estimate: None,
+            notes: String::new(),
+        }
+    }
+
+    fn from_row(row: &Row) -> Result<Todo> {
+        let tags: String = row.get(8)?;
+        Ok(Todo {
+            id: row.get(0)?,
+            title: row.get(1)?,
+            done: row.get(2)?,
+            priority: row.get(3)?,
+            created_at: row.get(4)?,
+            updated_at: row.get(5)?,
+            due_at: row.get(6)?,
+            project: row.get(7)?,
+            tags: tags.split_whitespace().map(|t| t.to_string()).collect(),
+            uuid: row.get(9)?,
+            scheduled_at: row.get(10)?,
+            parent_id: row.get(11)?,
+            context: row.get(12)?,
+            estimate: row.get(13)?,
+            notes: row.get(14)?,
+        })
+    }
+}
+
+// What adding a todo did, depending on the duplicate policy
+#[derive(Debug, PartialEq)]
+pub enum Added {
+    // Inserted, after completing the open todos with the same title
+    New { id: i32, completed: Vec<i32> },
+    Rejected { id: i32 },
+    Merged { id: i32, priority: i32 },
+}
+
+// A todo being added, everything but the title is optional
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct NewTodo {
+    pub(crate) title: String,
+    pub(crate) priority: Option<i32>,
+    pub(crate) due_at: Option<String>,
+    pub(crate) tags: Vec<String>,
+    pub(crate) context: Option<String>,
+    pub(crate) estimate: Option<i64>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Transition {
+    pub(crate) done: bool,
+    pub(crate) at: String,
+}
+
+#[derive(Clone, Debug)]
+pub struct Session {
+    pub(crate) todo_id: i32,
+    pub(crate) title: String,
+    pub(crate) started_at: String,
+    pub(crate) duration: i64,
+    pub(crate) completed: bool,
+}
+
+impl Session {
+    fn from_row(row: &Row) -> Result<Session> {
+        Ok(Session {
+            todo_id: row.get(0)?,
+            title: row.get(1)?,
+            started_at: row.get(2)?,
+            duration: row.get(3)?,
+            completed: row.get(4)?,
+        })
+    }
+}
+#[derive(Clone, Debug)]
+pub struct BackgroundTimer {
+    pub(crate) id: i32,
+    pub(crate) todo_id: Option<i32>,
+    pub(crate) started_at: String,
+    pub(crate) ends_at: String,
+    pub(crate) status: String,
 }
 
+impl BackgroundTimer {
+    fn from_row(row: &Row) -> Result<BackgroundTimer> {
+        Ok(BackgroundTimer {
+            id: row.get(0)?,
+            todo_id: row.get(1)?,
+            started_at: row.get(2)?,
+            ends_at: row.get(3)?,
+            status: row.get(4)?,
+        })
+    }
+}
 
 #[cfg(test)]
 mod tests {
@@ -246,13 +828,20 @@ mod tests {
 
     use super::*;
 
+    impl TodoDatabase {
+        fn add_todo(&self, todo: &str, priority: Option<i32>) -> Result<()> {
+            let todo = NewTodo { title: todo.to_string(), priority, ..NewTodo::default() };
+            self.add_new_todo(&todo, DuplicatePolicy::CompletePrevious).map(|_| ())
+        }
+    }
+
     #[allow(dead_code)]
     fn setup_test_db(db_name: &str) -> TodoDatabase {
         let db_path = format!("{}/.tc_test", env::var("HOME").unwrap()).to_string();
         let tdb = TodoDatabase::new0(db_path, db_name.to_string());
         tdb.initialize().expect("Failed to initialize test database");
 
-        return tdb;
+        tdb
     }
 
     #[allow(dead_code)]
@@ -293,7 +882,7 @@ mod tests {
         assert_eq!(todos.len(), 1);
         let todo_id = todos[0].id;
 
-        tdb.mark_as_done(&vec![todo_id]).unwrap();
+        tdb.mark_as_done(&[todo_id]).unwrap();
 
         let todos = tdb.list_todos(false).unwrap();
         assert_eq!(todos.len(), 0);
@@ -312,12 +901,12 @@ mod tests {
         assert_eq!(todos.len(), 1);
         let todo_id = todos[0].id;
 
-        tdb.mark_as_done(&vec![todo_id]).unwrap();
+        tdb.mark_as_done(&[todo_id]).unwrap();
 
         let todos = tdb.list_todos(false).unwrap();
         assert_eq!(todos.len(), 0);
 
-        tdb.mark_as_undone(&vec![todo_id]).unwrap();
+        tdb.mark_as_undone(&[todo_id]).unwrap();
 
         let todos = tdb.list_todos(false).unwrap();
         assert_eq!(todos.len(), 1);
@@ -336,7 +925,7 @@ mod tests {
         assert_eq!(todos.len(), 1);
         let todo_id = todos[0].id;
 
-        tdb.remove_todos_by_ids(&vec![todo_id]).unwrap();
+        tdb.remove_todos_by_ids(&[todo_id]).unwrap();
 
         let todos = tdb.list_todos(false).unwrap();
         assert_eq!(todos.len(), 0);
@@ -344,6 +933,108 @@ mod tests {
         tear_down_test_db(&tdb);
     }
 
+    #[test]
+    fn test_multiple_ids() {
+        let tdb = setup_test_db("test_multiple_ids.db");
+
+        for title in ["Todo 1", "Todo 2", "Todo 3", "Todo 4"] {
+            tdb.add_todo(title, None).unwrap();
+        }
+        let ids: Vec<i32> = tdb.list_todos(false).unwrap().iter().map(|todo| todo.id).collect();
+
+        tdb.mark_as_done(&ids[..3]).unwrap();
+        assert_eq!(tdb.list_todos(false).unwrap().len(), 1);
+        tdb.mark_as_undone(&ids[1..3]).unwrap();
+        assert_eq!(tdb.list_todos(false).unwrap().len(), 3);
+        tdb.remove_todos_by_ids(&ids[..2]).unwrap();
+        assert_eq!(tdb.list_todos(true).unwrap().len(), 2);
+
+        tear_down_test_db(&tdb);
+    }
+
+    #[test]
+    fn test_transitions() {
+        let tdb = setup_test_db("test_transitions.db");
+
+        tdb.add_todo("Buy milk", None).unwrap();
+        tdb.add_todo("Call mom", None).unwrap();
+        let ids: Vec<i32> = tdb.list_todos(false).unwrap().iter().map(|todo| todo.id).collect();
+
+        tdb.mark_as_done(&ids).unwrap();
+        // Marking a done todo as done again isn't a transition
+        tdb.mark_as_done(&ids[..1]).unwrap();
+        tdb.mark_as_undone(&ids[..1]).unwrap();
+
+        let transitions: Vec<bool> = tdb.list_transitions(ids[0]).unwrap().iter().map(|transition| transition.done).collect();
+        assert_eq!(transitions, vec![true, false]);
+        assert_eq!(tdb.list_transitions(ids[1]).unwrap().len(), 1);
+
+        tdb.remove_todos_by_ids(&ids).unwrap();
+        assert!(tdb.list_transitions(ids[0]).unwrap().is_empty());
+
+        tear_down_test_db(&tdb);
+    }
+
+    #[test]
+    fn test_stale_todos() {
+        let tdb = setup_test_db("test_stale_todos.db");
+
+        for title in ["Old task", "Reviewed task", "Done task", "New task"] {
+            tdb.add_todo(title, None).unwrap();
+        }
+        let conn = Connection::open(tdb.get_db_path()).unwrap();
+        conn.execute("UPDATE todos SET updated_at = '2026-01-01T00:00:00Z' WHERE title != 'New task'", []).unwrap();
+        let ids: Vec<i32> = tdb.list_todos(false).unwrap().iter().map(|todo| todo.id).collect();
+        tdb.mark_reviewed(ids[1]).unwrap();
+        conn.execute("UPDATE todos SET done = 1 WHERE id = ?1", params![ids[2]]).unwrap();
+
+        let stale = tdb.list_stale_todos("2026-06-01T00:00:00Z").unwrap();
+        assert_eq!(stale.iter().map(|todo| todo.title.as_str()).collect::<Vec<_>>(), vec!["Old task"]);
+        // Reviewing doesn't count as an update
+        assert_eq!(tdb.get_todo(ids[1]).unwrap().unwrap().updated_at, "2026-01-01T00:00:00Z");
+
+        assert!(tdb.set_due(ids[0], Some("2026-10-23T00:00:00Z")).unwrap());
+        assert!(!tdb.set_due(-1, None).unwrap());
+        assert!(tdb.list_stale_todos("2026-06-01T00:00:00Z").unwrap().is_empty());
+
+        tear_down_test_db(&tdb);
+    }
+
+    #[test]
+    fn test_apply_edits() {
+        let tdb = setup_test_db("test_apply_edits.db");
+
+        for title in ["Buy milk", "Call mom", "Old task"] {
+            tdb.add_todo(title, None).unwrap();
+        }
+        let todos = tdb.list_todos(false).unwrap();
+
+        let mut updated = todos[0].clone();
+        updated.title = "Buy oat milk".to_string();
+        updated.priority = 2;
+        let mut added = todos[1].clone();
+        added.title = "Pay rent".to_string();
+        added.done = true;
+        assert!(tdb.apply_edits(&[updated], &[added], &todos[1..]).unwrap().is_empty());
+
+        let todos = tdb.list_todos(true).unwrap();
+        assert_eq!(todos.len(), 2);
+        assert_eq!((todos[0].title.as_str(), todos[0].priority), ("Buy oat milk", 2));
+        assert!(todos[1].done);
+        assert_eq!(todos[1].title, "Pay rent");
+
+        // Nothing is applied when a todo changed after it was written out for editing
+        let mut stale = todos[0].clone();
+        stale.updated_at = "2026-01-01T00:00:00Z".to_string();
+        let mut renamed = todos[1].clone();
+        renamed.title = "Pay the rent".to_string();
+        assert_eq!(tdb.apply_edits(&[renamed], &[], &[stale]).unwrap(), vec![todos[0].id]);
+        assert_eq!(tdb.list_todos(true).unwrap().len(), 2);
+        assert_eq!(tdb.get_todo(todos[1].id).unwrap().unwrap().title, "Pay rent");
+
+        tear_down_test_db(&tdb);
+    }
+
     #[test]
     fn test_add_remove_until_date_and_list_todos() {
         let tdb = setup_test_db("test_add_remove_until_date_and_list_todos.db");
@@ -354,8 +1045,8 @@ mod tests {
         let todos = tdb.list_todos(false).unwrap();
         assert_eq!(todos.len(), 1);
 
-        let date = chrono::Local::now().format("%Y-%m-%d").to_string();
-        tdb.remove_todos_until_date(&date).unwrap();
+        let until = chrono::Local::now().date_naive().and_hms_opt(23, 59, 59).unwrap();
+        tdb.remove_todos_until_date(until).unwrap();
 
         let todos = tdb.list_todos(false).unwrap();
         assert_eq!(todos.len(), 0);
@@ -373,9 +1064,308 @@ mod tests {
         let todos = tdb.list_todos(false).unwrap();
         assert_eq!(todos.len(), 1);
         assert_eq!(todos[0].title, todo);
-        assert_eq!(todos[0].done, false);
+        assert!(!todos[0].done);
         assert_eq!(todos[0].priority, 0);
 
         tear_down_test_db(&tdb);
     }
+
+    #[test]
+    fn test_find_todos_with_quotes() {
+        let tdb = setup_test_db("test_find_todos_with_quotes.db");
+
+        tdb.add_todo("Call mom's dentist", None).unwrap();
+        tdb.add_todo("Buy milk", None).unwrap();
+
+        let todos = tdb.find_todos("mom's", false).unwrap();
+        assert_eq!(todos.len(), 1);
+        assert_eq!(todos[0].title, "Call mom's dentist");
+
+        tear_down_test_db(&tdb);
+    }
+
+    #[test]
+    fn test_find_todos_with_wildcards() {
+        let tdb = setup_test_db("test_find_todos_with_wildcards.db");
+
+        tdb.add_todo("Charge phone to 100%", None).unwrap();
+        tdb.add_todo("Charge 1000 coins", None).unwrap();
+        tdb.add_todo("Rename file_a", None).unwrap();
+        tdb.add_todo("Rename fileXa", None).unwrap();
+
+        assert_eq!(tdb.find_todos("100%", false).unwrap().len(), 1);
+        assert_eq!(tdb.find_todos("file_a", false).unwrap().len(), 1);
+        assert_eq!(tdb.find_todos("Rename", false).unwrap().len(), 2);
+
+        tear_down_test_db(&tdb);
+    }
+
+    #[test]
+    fn test_find_todos_in_notes() {
+        let tdb = setup_test_db("test_find_todos_in_notes.db");
+
+        tdb.add_todo("Prepare the release", None).unwrap();
+        tdb.add_todo("Buy milk", None).unwrap();
+        let todo_id = tdb.list_todos(false).unwrap()[0].id;
+
+        assert!(tdb.set_notes(todo_id, "Bump the version\nTag the changelog").unwrap());
+        assert!(!tdb.set_notes(todo_id + 100, "Nothing").unwrap());
+
+        let todos = tdb.find_todos("changelog", false).unwrap();
+        assert_eq!(todos.len(), 1);
+        assert_eq!(todos[0].notes, "Bump the version\nTag the changelog");
+
+        tear_down_test_db(&tdb);
+    }
+
+    #[test]
+    fn test_update_priority() {
+        let tdb = setup_test_db("test_update_priority.db");
+
+        tdb.add_todo("Test Todo", Some(1)).unwrap();
+        let todo_id = tdb.list_todos(false).unwrap()[0].id;
+
+        tdb.update_priority(todo_id, 3).unwrap();
+        assert_eq!(tdb.list_todos(false).unwrap()[0].priority, 3);
+
+        tear_down_test_db(&tdb);
+    }
+
+    #[test]
+    fn test_import_todo_updates_todo_with_same_uuid() {
+        let tdb = setup_test_db("test_import_todo_updates_todo_with_same_uuid.db");
+
+        let now = dates::now_timestamp();
+        let mut todo = Todo {
+            id: 0,
+            title: "Imported Todo".to_string(),
+            done: false,
+            priority: 2,
+            created_at: now.clone(),
+            updated_at: now,
+            due_at: None,
+            project: Some("work".to_string()),
+            tags: vec!["a".to_string(), "b".to_string()],
+            uuid: Some("6f1f9bb4-6d0c-4d2a-8f5e-2f6a3f1f0c44".to_string()),
+            scheduled_at: None,
+            parent_id: None,
+            context: None,
+            estimate: None,
+            notes: String::new(),
+        };
+        let id = tdb.import_todos(&[(todo.clone(), None)]).unwrap()[0];
+
+        todo.done = true;
+        assert_eq!(tdb.import_todos(&[(todo.clone(), None)]).unwrap(), vec![id]);
+
+        let todos = tdb.list_todos(true).unwrap();
+        assert_eq!(todos.len(), 1);
+        assert!(todos[0].done);
+        assert_eq!(todos[0].project.as_deref(), Some("work"));
+        assert_eq!(todos[0].tags, vec!["a".to_string(), "b".to_string()]);
+
+        // A scoped import never overwrites the todo of another project, and imported todos land in the project
+        let project_tdb = setup_test_db("test_import_todo_updates_todo_with_same_uuid.db").with_project("tc");
+        todo.title = "Overwritten".to_string();
+        assert!(project_tdb.import_todos(&[(todo.clone(), None)]).is_err());
+        assert_eq!(tdb.get_todo(id).unwrap().unwrap().title, "Imported Todo");
+
+        todo.uuid = None;
+        let imported = project_tdb.import_todos(&[(todo, None)]).unwrap()[0];
+        assert_eq!(project_tdb.get_todo(imported).unwrap().unwrap().project.as_deref(), Some("tc"));
+
+        tear_down_test_db(&tdb);
+    }
+
+    #[test]
+    fn test_import_todos_all_or_nothing() {
+        let tdb = setup_test_db("test_import_todos_all_or_nothing.db");
+
+        let ids = tdb.import_todos(&[(Todo::new(0, "Release"), None), (Todo::new(0, "Write changelog"), Some(0))]).unwrap();
+        assert_eq!(tdb.get_todo(ids[1]).unwrap().unwrap().parent_id, Some(ids[0]));
+
+        // Inserting the second todo fails, the first one is rolled back with it
+        let conn = Connection::open(tdb.get_db_path()).unwrap();
+        conn.execute("CREATE TRIGGER reject_broken BEFORE INSERT ON todos WHEN NEW.title = 'Broken' BEGIN SELECT RAISE(ABORT, 'broken'); END", []).unwrap();
+        assert!(tdb.import_todos(&[(Todo::new(0, "Tag release"), None), (Todo::new(0, "Broken"), Some(0))]).is_err());
+        assert_eq!(tdb.list_todos(true).unwrap().len(), 2);
+
+        tear_down_test_db(&tdb);
+    }
+
+    #[test]
+    fn test_add_and_list_sessions() {
+        let tdb = setup_test_db("test_add_and_list_sessions.db");
+
+        tdb.add_todo("Test Todo", None).unwrap();
+        let todo_id = tdb.list_todos(false).unwrap()[0].id;
+
+        tdb.add_session(todo_id, "2024-01-01T09:00:00+00:00", "2024-01-01T09:25:00+00:00", 1500, true).unwrap();
+        tdb.add_session(todo_id, "2024-01-02T09:00:00+00:00", "2024-01-02T09:10:00+00:00", 600, false).unwrap();
+
+        let sessions = tdb.list_sessions(todo_id).unwrap();
+        assert_eq!(sessions.len(), 2);
+        assert_eq!(sessions[0].title, "Test Todo");
+        assert!(!sessions[1].completed);
+
+        let sessions = tdb.list_sessions_between("2024-01-02T00:00:00+00:00", "2024-01-03T00:00:00+00:00").unwrap();
+        assert_eq!(sessions.len(), 1);
+        assert_eq!(sessions[0].duration, 600);
+
+        tear_down_test_db(&tdb);
+    }
+
+    #[test]
+    fn test_removed_todo_ids_start_without_sessions() {
+        let tdb = setup_test_db("test_removed_todo_ids_start_without_sessions.db");
+
+        tdb.add_todo("Old task", None).unwrap();
+        let todo_id = tdb.list_todos(false).unwrap()[0].id;
+        tdb.add_session(todo_id, "2024-01-01T09:00:00Z", "2024-01-01T09:25:00Z", 1500, true).unwrap();
+        tdb.add_timer(Some(todo_id), "2024-01-01T09:00:00Z", "2024-01-01T09:25:00Z").unwrap();
+        tdb.remove_todos_by_ids(&[todo_id]).unwrap();
+
+        // SQLite hands out the id of the removed newest todo again
+        tdb.add_todo("New task", None).unwrap();
+        let new_id = tdb.list_todos(false).unwrap()[0].id;
+        assert_eq!(new_id, todo_id);
+        assert!(tdb.list_sessions(new_id).unwrap().is_empty());
+        assert!(tdb.list_running_timers().unwrap().is_empty());
+
+        tdb.add_session(new_id, "2024-01-02T09:00:00Z", "2024-01-02T09:25:00Z", 1500, true).unwrap();
+        tdb.remove_todos_until_date(chrono::Local::now().naive_local()).unwrap();
+        tdb.add_todo("Newer task", None).unwrap();
+        assert!(tdb.list_sessions(tdb.list_todos(false).unwrap()[0].id).unwrap().is_empty());
+
+        tear_down_test_db(&tdb);
+    }
+
+    #[test]
+    fn test_finish_timer_only_once() {
+        let tdb = setup_test_db("test_finish_timer_only_once.db");
+
+        let id = tdb.add_timer(None, "2024-01-01T09:00:00+00:00", "2024-01-01T09:25:00+00:00").unwrap();
+        assert_eq!(tdb.list_running_timers().unwrap().len(), 1);
+
+        assert!(tdb.finish_timer(id, TIMER_FIRED).unwrap());
+        assert!(!tdb.finish_timer(id, TIMER_CANCELLED).unwrap());
+        assert_eq!(tdb.get_timer(id).unwrap().unwrap().status, TIMER_FIRED);
+        assert!(tdb.list_running_timers().unwrap().is_empty());
+
+        tear_down_test_db(&tdb);
+    }
+
+    #[test]
+    fn test_duplicate_policies() {
+        let tdb = setup_test_db("test_duplicate_policies.db");
+        let todo = |priority: Option<i32>, tags: &[&str]| NewTodo {
+            title: "Buy milk".to_string(),
+            priority,
+            tags: tags.iter().map(|tag| tag.to_string()).collect(),
+            ..NewTodo::default()
+        };
+
+        let Added::New { id, .. } = tdb.add_new_todo(&todo(None, &["home"]), DuplicatePolicy::Allow).unwrap() else {
+            panic!("Todo was not added");
+        };
+        assert_eq!(tdb.add_new_todo(&todo(None, &[]), DuplicatePolicy::Reject).unwrap(), Added::Rejected { id });
+        assert_eq!(tdb.add_new_todo(&todo(Some(1), &["store"]), DuplicatePolicy::MergeAndBumpPriority).unwrap(), Added::Merged { id, priority: 1 });
+        assert_eq!(tdb.add_new_todo(&todo(Some(5), &[]), DuplicatePolicy::MergeAndBumpPriority).unwrap(), Added::Merged { id, priority: 5 });
+
+        let todos = tdb.list_todos(false).unwrap();
+        assert_eq!(todos.len(), 1);
+        assert_eq!(todos[0].tags, vec!["home".to_string(), "store".to_string()]);
+
+        tdb.add_new_todo(&todo(None, &[]), DuplicatePolicy::Allow).unwrap();
+        assert_eq!(tdb.list_todos(false).unwrap().len(), 2);
+
+        let Added::New { completed, .. } = tdb.add_new_todo(&todo(None, &[]), DuplicatePolicy::CompletePrevious).unwrap() else {
+            panic!("Todo was not added");
+        };
+        assert_eq!(completed.len(), 2);
+        assert_eq!(tdb.list_todos(false).unwrap().len(), 1);
+
+        tear_down_test_db(&tdb);
+    }
+
+    #[test]
+    fn test_migrate_timestamps() {
+        let tdb = setup_test_db("test_migrate_timestamps.db");
+
+        let conn = Connection::open(tdb.get_db_path()).unwrap();
+        conn.execute(
+            "INSERT INTO todos (title, done, priority, created_at, updated_at, due_at) VALUES ('Buy milk', 0, 0, ?1, ?1, ?2)",
+            params!["2026-10-18T09:30:00+02:00", "2026-10-20T00:00:00Z"],
+        )
+        .unwrap();
+        conn.execute("INSERT INTO timers (started_at, ends_at, status) VALUES (?1, ?1, 'fired')", params!["2026-10-18T01:00:00+02:00"]).unwrap();
+        conn.execute("PRAGMA user_version = 0", []).unwrap();
+
+        tdb.initialize().unwrap();
+        let todo = tdb.list_todos(false).unwrap().remove(0);
+        assert_eq!((todo.created_at.as_str(), todo.updated_at.as_str()), ("2026-10-18T07:30:00Z", "2026-10-18T07:30:00Z"));
+        assert_eq!(todo.due_at.as_deref(), Some("2026-10-20T00:00:00Z"));
+        let started_at: String = conn.query_row("SELECT started_at FROM timers", [], |row| row.get(0)).unwrap();
+        assert_eq!(started_at, "2026-10-17T23:00:00Z");
+        let version: i32 = conn.query_row("PRAGMA user_version", [], |row| row.get(0)).unwrap();
+        assert_eq!(version, TIMESTAMPS_IN_UTC);
+
+        tear_down_test_db(&tdb);
+    }
+
+    #[test]
+    fn test_project_scope() {
+        let tdb = setup_test_db("test_project_scope.db");
+        let project_tdb = setup_test_db("test_project_scope.db").with_project("tc");
+
+        tdb.add_todo("Buy milk", None).unwrap();
+        project_tdb.add_todo("Fix the parser", None).unwrap();
+        // A todo with the same title outside the project is left alone
+        project_tdb.add_todo("Buy milk", None).unwrap();
+
+        let titles = |todos: Vec<Todo>| todos.into_iter().map(|todo| todo.title).collect::<Vec<String>>();
+        assert_eq!(titles(project_tdb.list_todos(false).unwrap()), vec!["Fix the parser", "Buy milk"]);
+        assert_eq!(titles(tdb.list_todos(false).unwrap()).len(), 3);
+        assert!(project_tdb.find_todos("milk", false).unwrap().iter().all(|todo| todo.project.as_deref() == Some("tc")));
+
+        // Todos outside the project are not found by id
+        tdb.add_todo("Call mom", None).unwrap();
+        let outside = tdb.list_todos(false).unwrap().last().unwrap().id;
+        let inside = project_tdb.list_todos(false).unwrap()[0].id;
+        assert!(project_tdb.get_todo(outside).unwrap().is_none());
+        assert!(!project_tdb.update_todo(outside, "Call dad").unwrap());
+        assert!(!project_tdb.set_notes(outside, "Sunday").unwrap());
+        assert!(!project_tdb.update_priority(outside, 3).unwrap());
+        assert_eq!(project_tdb.mark_as_done(&[inside, outside]).unwrap(), vec![outside]);
+        assert_eq!(project_tdb.mark_as_undone(&[outside]).unwrap(), vec![outside]);
+        assert_eq!(project_tdb.remove_todos_by_ids(&[outside]).unwrap(), vec![outside]);
+        let todo = tdb.get_todo(outside).unwrap().unwrap();
+        assert_eq!((todo.title.as_str(), todo.priority, todo.done, todo.notes.as_str()), ("Call mom", 0, false, ""));
+        assert!(project_tdb.get_todo(inside).unwrap().unwrap().done);
+
+        // Sessions and timers follow the project of their todo
+        tdb.add_session(outside, "2024-01-01T09:00:00Z", "2024-01-01T09:25:00Z", 1500, true).unwrap();
+        tdb.add_session(inside, "2024-01-01T10:00:00Z", "2024-01-01T10:25:00Z", 1500, true).unwrap();
+        let sessions = project_tdb.list_sessions_between("2024-01-01T00:00:00Z", "2024-01-02T00:00:00Z").unwrap();
+        assert_eq!(sessions.iter().map(|session| session.todo_id).collect::<Vec<i32>>(), vec![inside]);
+        assert_eq!(tdb.list_sessions_between("2024-01-01T00:00:00Z", "2024-01-02T00:00:00Z").unwrap().len(), 2);
+        assert!(project_tdb.list_sessions(outside).unwrap().is_empty());
+
+        let outside_timer = tdb.add_timer(Some(outside), "2024-01-01T09:00:00Z", "2024-01-01T09:25:00Z").unwrap();
+        let inside_timer = tdb.add_timer(Some(inside), "2024-01-01T09:00:00Z", "2024-01-01T09:30:00Z").unwrap();
+        let free_timer = tdb.add_timer(None, "2024-01-01T09:00:00Z", "2024-01-01T09:35:00Z").unwrap();
+        let timer_ids = |tdb: &TodoDatabase| tdb.list_running_timers().unwrap().into_iter().map(|timer| timer.id).collect::<Vec<i32>>();
+        assert_eq!(timer_ids(&project_tdb), vec![inside_timer, free_timer]);
+        assert_eq!(timer_ids(&tdb), vec![outside_timer, inside_timer, free_timer]);
+
+        // Without a project every todo is found
+        assert!(tdb.update_todo(inside, "Fix the lexer").unwrap());
+        assert!(tdb.remove_todos_by_ids(&[outside]).unwrap().is_empty());
+
+        project_tdb.reset().unwrap();
+        assert!(project_tdb.list_todos(true).unwrap().is_empty());
+        assert_eq!(titles(tdb.list_todos(true).unwrap()), vec!["Buy milk"]);
+
+        tear_down_test_db(&tdb);
+    }
 }
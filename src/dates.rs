@@ -0,0 +1,276 @@
+use chrono::{DateTime, Datelike, Days, Duration, Local, Months, NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Utc, Weekday};
+use regex::Regex;
+
+// Examples shown when a date doesn't parse
+pub const DATE_EXAMPLES: &str = "today, yesterday, tomorrow, fri, last fri, next week, in 3 days, -2w, 2026-11-01 or 2026-11-01 10:00";
+
+// A date typed on the command line, with the time of day when one was given
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ParsedDate {
+    pub date: NaiveDate,
+    pub time: Option<NaiveTime>,
+}
+
+impl ParsedDate {
+    // The given time, or the start of the day
+    pub fn start(&self) -> NaiveDateTime {
+        self.date.and_time(self.time.unwrap_or(NaiveTime::MIN))
+    }
+
+    // The given time, or the last second of the day, e.g. to include everything up to a date
+    pub fn end(&self) -> NaiveDateTime {
+        self.date.and_time(self.time.unwrap_or_else(|| NaiveTime::from_hms_opt(23, 59, 59).unwrap()))
+    }
+}
+
+// Parses the dates every command accepts, relative to `now`:
+// - `now`, `today`, `yesterday` and `tomorrow`
+// - weekday names such as `fri` or `friday`, the next one (never today) unless prefixed with `last`
+// - `next week` and `last week`, their monday
+// - `in 3 days`, `2 weeks ago` and the short `+3d`, `-2w`, `-1m` and `+1y`
+// - ISO dates and datetimes like `2026-11-01`, `2026-11-01 10:00` and `2026-11-01T10:00:00+02:00`
+pub fn parse(text: &str, now: NaiveDateTime) -> Option<ParsedDate> {
+    let text = text.trim().to_lowercase();
+    let today = now.date();
+    let date = |date: NaiveDate| Some(ParsedDate { date, time: None });
+
+    match text.as_str() {
+        "now" => return Some(ParsedDate { date: today, time: Some(now.time()) }),
+        "today" => return date(today),
+        "yesterday" => return date(today.pred_opt()?),
+        "tomorrow" => return date(today.succ_opt()?),
+        "next week" => return date(week_start(today) + Duration::weeks(1)),
+        "last week" => return date(week_start(today) - Duration::weeks(1)),
+        _ => {}
+    }
+
+    if let Some(date) = parse_weekday(&text, today) {
+        return Some(ParsedDate { date, time: None });
+    }
+    if let Some(date) = parse_offset(&text, today) {
+        return Some(ParsedDate { date, time: None });
+    }
+    parse_iso(&text)
+}
+
+fn week_start(date: NaiveDate) -> NaiveDate {
+    date - Duration::days(date.weekday().num_days_from_monday() as i64)
+}
+
+fn parse_weekday(text: &str, today: NaiveDate) -> Option<NaiveDate> {
+    let (past, name) = match text.split_once(' ') {
+        Some(("last", name)) => (true, name),
+        Some(("next", name)) => (false, name),
+        Some(_) => return None,
+        None => (false, text),
+    };
+    // chrono accepts the short and the full name, e.g. `fri` and `friday`
+    let weekday = name.parse::<Weekday>().ok()?;
+
+    let from_monday = |weekday: Weekday| weekday.num_days_from_monday() as i64;
+    if past {
+        let days_back = (from_monday(today.weekday()) - from_monday(weekday)).rem_euclid(7);
+        Some(today - Duration::days(if days_back == 0 { 7 } else { days_back }))
+    } else {
+        let days_ahead = (from_monday(weekday) - from_monday(today.weekday())).rem_euclid(7);
+        Some(today + Duration::days(if days_ahead == 0 { 7 } else { days_ahead }))
+    }
+}
+
+// `in 3 days`, `2 weeks ago`, `+3d` or `-2w`
+fn parse_offset(text: &str, today: NaiveDate) -> Option<NaiveDate> {
+    let long_regex = Regex::new(r"^(?:in (\d+) (day|week|month|year)s?|(\d+) (day|week|month|year)s? ago)$").unwrap();
+    let short_regex = Regex::new(r"^([+-])(\d+)([dwmy])$").unwrap();
+
+    let (sign, amount, unit) = if let Some(captures) = long_regex.captures(text) {
+        match captures.get(1) {
+            Some(amount) => (1, amount.as_str().parse::<u32>().ok()?, captures[2].to_string()),
+            None => (-1, captures[3].parse::<u32>().ok()?, captures[4].to_string()),
+        }
+    } else {
+        let captures = short_regex.captures(text)?;
+        let sign = if &captures[1] == "-" { -1 } else { 1 };
+        (sign, captures[2].parse::<u32>().ok()?, captures[3].to_string())
+    };
+
+    match (unit.chars().next()?, sign) {
+        ('d', 1) => today.checked_add_days(Days::new(amount as u64)),
+        ('d', _) => today.checked_sub_days(Days::new(amount as u64)),
+        ('w', 1) => today.checked_add_days(Days::new(amount as u64 * 7)),
+        ('w', _) => today.checked_sub_days(Days::new(amount as u64 * 7)),
+        ('m', 1) => today.checked_add_months(Months::new(amount)),
+        ('m', _) => today.checked_sub_months(Months::new(amount)),
+        ('y', 1) => today.checked_add_months(Months::new(amount.checked_mul(12)?)),
+        ('y', _) => today.checked_sub_months(Months::new(amount.checked_mul(12)?)),
+        _ => None,
+    }
+}
+
+fn parse_iso(text: &str) -> Option<ParsedDate> {
+    // The text was lowercased, undo it for the `T` separator and the `Z` offset
+    let text = text.to_uppercase();
+    if let Ok(date) = NaiveDate::parse_from_str(&text, "%Y-%m-%d") {
+        return Some(ParsedDate { date, time: None });
+    }
+    // Datetimes with an offset are converted to local time
+    if let Ok(date) = DateTime::parse_from_rfc3339(&text) {
+        let date = date.with_timezone(&Local).naive_local();
+        return Some(ParsedDate { date: date.date(), time: Some(date.time()) });
+    }
+    ["%Y-%m-%dT%H:%M:%S", "%Y-%m-%dT%H:%M", "%Y-%m-%d %H:%M:%S", "%Y-%m-%d %H:%M"]
+        .iter()
+        .find_map(|format| NaiveDateTime::parse_from_str(&text, format).ok())
+        .map(|date| ParsedDate { date: date.date(), time: Some(date.time()) })
+}
+
+// Timestamps are stored in UTC with second precision, e.g. `2026-10-19T08:05:12Z`. Comparing them as text
+// compares them in time whichever time zone they were written in, they're shown in the local zone (TZ).
+const TIMESTAMP_FORMAT: &str = "%Y-%m-%dT%H:%M:%SZ";
+
+pub fn format_timestamp<Tz: TimeZone>(date: &DateTime<Tz>) -> String {
+    date.with_timezone(&Utc).format(TIMESTAMP_FORMAT).to_string()
+}
+
+pub fn now_timestamp() -> String {
+    format_timestamp(&Utc::now())
+}
+
+// Timestamp of a local date and time, the earlier one when the clock was turned back
+pub fn to_timestamp(date: NaiveDateTime) -> String {
+    // A time skipped when the clock was turned forward is taken as the same moment an hour later
+    let local = Local
+        .from_local_datetime(&date)
+        .earliest()
+        .or_else(|| Local.from_local_datetime(&(date + Duration::hours(1))).earliest());
+    match local {
+        Some(local) => format_timestamp(&local),
+        None => format_timestamp(&date.and_utc()),
+    }
+}
+
+// Reads stored timestamps, including the ones with a local offset written by older versions
+pub fn parse_timestamp(timestamp: &str) -> Option<DateTime<Local>> {
+    DateTime::parse_from_rfc3339(timestamp).ok().map(|date| date.with_timezone(&Local))
+}
+
+pub fn local_date(timestamp: &str) -> Option<NaiveDate> {
+    parse_timestamp(timestamp).map(|date| date.date_naive())
+}
+
+// The local day of a timestamp, with the time unless it's midnight, e.g. `2026-10-23` or `2026-10-23 17:00`
+pub fn format_local(timestamp: &str) -> String {
+    match parse_timestamp(timestamp) {
+        Some(date) if date.time() == NaiveTime::MIN => date.format("%Y-%m-%d").to_string(),
+        Some(date) => date.format("%Y-%m-%d %H:%M").to_string(),
+        None => timestamp.to_string(),
+    }
+}
+
+// How long ago a timestamp was, or how far ahead it is, e.g. `3 days ago` or `in 2 hours`
+pub fn relative(timestamp: &str, now: DateTime<Utc>) -> Option<String> {
+    let seconds = (now - parse_timestamp(timestamp)?.with_timezone(&Utc)).num_seconds();
+    if seconds.abs() < 60 {
+        return Some("just now".to_string());
+    }
+
+    let units = [("year", 365 * 86400), ("month", 30 * 86400), ("week", 7 * 86400), ("day", 86400), ("hour", 3600), ("minute", 60)];
+    let (unit, length) = units.iter().find(|(_, length)| seconds.abs() >= *length)?;
+    let count = seconds.abs() / length;
+    let amount = format!("{} {}{}", count, unit, if count == 1 { "" } else { "s" });
+    Some(if seconds > 0 { format!("{} ago", amount) } else { format!("in {}", amount) })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Wednesday 2026-10-14 at 15:30
+    fn now() -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2026, 10, 14).unwrap().and_hms_opt(15, 30, 0).unwrap()
+    }
+
+    fn date(text: &str) -> Option<NaiveDate> {
+        parse(text, now()).map(|parsed| parsed.date)
+    }
+
+    fn ymd(year: i32, month: u32, day: u32) -> Option<NaiveDate> {
+        NaiveDate::from_ymd_opt(year, month, day)
+    }
+
+    #[test]
+    fn parse_relative_dates() {
+        assert_eq!(date("today"), ymd(2026, 10, 14));
+        assert_eq!(date("Yesterday"), ymd(2026, 10, 13));
+        assert_eq!(date("tomorrow"), ymd(2026, 10, 15));
+        assert_eq!(parse("now", now()).unwrap().start(), now());
+
+        assert_eq!(date("next week"), ymd(2026, 10, 19));
+        assert_eq!(date("last week"), ymd(2026, 10, 5));
+        assert_eq!(date("in 3 days"), ymd(2026, 10, 17));
+        assert_eq!(date("in 1 week"), ymd(2026, 10, 21));
+        assert_eq!(date("2 weeks ago"), ymd(2026, 9, 30));
+        assert_eq!(date("-2w"), ymd(2026, 9, 30));
+        assert_eq!(date("+3d"), ymd(2026, 10, 17));
+        assert_eq!(date("-1m"), ymd(2026, 9, 14));
+        assert_eq!(date("+1y"), ymd(2027, 10, 14));
+    }
+
+    #[test]
+    fn parse_weekdays() {
+        assert_eq!(date("fri"), ymd(2026, 10, 16));
+        assert_eq!(date("Monday"), ymd(2026, 10, 19));
+        assert_eq!(date("wed"), ymd(2026, 10, 21));
+        assert_eq!(date("next wed"), ymd(2026, 10, 21));
+        assert_eq!(date("last wed"), ymd(2026, 10, 7));
+        assert_eq!(date("last mon"), ymd(2026, 10, 12));
+        assert_eq!(date("last fri"), ymd(2026, 10, 9));
+    }
+
+    #[test]
+    fn parse_iso_dates() {
+        let parsed = parse("2026-11-01", now()).unwrap();
+        assert_eq!(parsed, ParsedDate { date: ymd(2026, 11, 1).unwrap(), time: None });
+        assert_eq!(parsed.end(), ymd(2026, 11, 1).unwrap().and_hms_opt(23, 59, 59).unwrap());
+
+        let parsed = parse("2026-11-01 10:00", now()).unwrap();
+        assert_eq!(parsed.start(), ymd(2026, 11, 1).unwrap().and_hms_opt(10, 0, 0).unwrap());
+        assert_eq!(parsed.end(), parsed.start());
+        assert_eq!(parse("2026-11-01T10:00:30", now()).unwrap().time, NaiveTime::from_hms_opt(10, 0, 30));
+        assert!(parse("2026-11-01T10:00:00+02:00", now()).is_some());
+
+        assert_eq!(date("someday"), None);
+        assert_eq!(date("2026-13-01"), None);
+        assert_eq!(date("in 3 fortnights"), None);
+    }
+
+    #[test]
+    fn timestamps_are_utc() {
+        let date = DateTime::parse_from_rfc3339("2026-10-14T15:30:00.123456+02:00").unwrap();
+        assert_eq!(format_timestamp(&date), "2026-10-14T13:30:00Z");
+        assert!(now_timestamp().ends_with('Z'));
+
+        // Timestamps written with an offset by older versions read the same as the UTC ones
+        assert_eq!(parse_timestamp("2026-10-14T15:30:00+02:00"), parse_timestamp("2026-10-14T13:30:00Z"));
+        assert_eq!(parse_timestamp("yesterday"), None);
+
+        let local = now();
+        let timestamp = to_timestamp(local);
+        assert_eq!(parse_timestamp(&timestamp).unwrap().naive_local(), local);
+        assert_eq!(local_date(&timestamp), Some(local.date()));
+        assert_eq!(format_local(&timestamp), "2026-10-14 15:30");
+        assert_eq!(format_local(&to_timestamp(local.date().and_time(NaiveTime::MIN))), "2026-10-14");
+    }
+
+    #[test]
+    fn relative_ages() {
+        let now = DateTime::parse_from_rfc3339("2026-10-14T15:30:00Z").unwrap().with_timezone(&Utc);
+        assert_eq!(relative("2026-10-14T15:29:30Z", now).as_deref(), Some("just now"));
+        assert_eq!(relative("2026-10-14T15:25:00Z", now).as_deref(), Some("5 minutes ago"));
+        assert_eq!(relative("2026-10-14T14:30:00Z", now).as_deref(), Some("1 hour ago"));
+        assert_eq!(relative("2026-10-11T15:30:00Z", now).as_deref(), Some("3 days ago"));
+        assert_eq!(relative("2026-09-30T15:30:00Z", now).as_deref(), Some("2 weeks ago"));
+        assert_eq!(relative("2025-10-01T15:30:00Z", now).as_deref(), Some("1 year ago"));
+        assert_eq!(relative("2026-10-16T17:30:00Z", now).as_deref(), Some("in 2 days"));
+        assert_eq!(relative("someday", now), None);
+    }
+}
@@ -0,0 +1,2 @@
+pub(crate) mod org;
+pub(crate) mod taskwarrior;
@@ -0,0 +1,272 @@
+use std::collections::{HashMap, HashSet};
+
+use chrono::{NaiveDate, NaiveDateTime, NaiveTime, Timelike};
+use regex::Regex;
+
+use crate::database::Todo;
+use crate::dates;
+
+// Parses an org file into todos, each paired with the index of its parent headline in the returned list.
+// Every headline becomes a todo, `DONE` marks it as done and any other keyword (or none) leaves it open.
+pub fn parse(input: &str) -> Result<Vec<(Todo, Option<usize>)>, String> {
+    let headline = Regex::new(r"^(\*+)\s+(?:(TODO|DONE)(?:\s+|$))?(?:\[#([A-Za-z])\]\s*)?(.*?)(?:\s+:([\w@#%:]+):)?\s*$").unwrap();
+    let planning = Regex::new(r"(SCHEDULED|DEADLINE|CLOSED):\s*[<\[]([^>\]]+)[>\]]").unwrap();
+    let property = Regex::new(r"^\s*:(ID|CATEGORY):\s*(\S.*?)\s*$").unwrap();
+
+    let now = dates::now_timestamp();
+    let mut entries: Vec<(Todo, Option<usize>)> = Vec::new();
+    // (level, index) of the headlines enclosing the current line
+    let mut ancestors: Vec<(usize, usize)> = Vec::new();
+
+    for (line_number, line) in input.lines().enumerate() {
+        if let Some(captures) = headline.captures(line) {
+            let level = captures[1].len();
+            while ancestors.last().is_some_and(|(l, _)| *l >= level) {
+                ancestors.pop();
+            }
+            let parent = ancestors.last().map(|(_, index)| *index);
+
+            let todo = Todo {
+                id: 0,
+                title: captures[4].to_string(),
+                done: captures.get(2).is_some_and(|keyword| keyword.as_str() == "DONE"),
+                priority: captures.get(3).map_or(0, |cookie| priority_from_cookie(cookie.as_str())),
+                created_at: now.clone(),
+                updated_at: now.clone(),
+                due_at: None,
+                project: None,
+                tags: captures.get(5).map_or(Vec::new(), |tags| {
+                    tags.as_str().split(':').filter(|t| !t.is_empty()).map(|t| t.to_string()).collect()
+                }),
+                uuid: None,
+                scheduled_at: None,
+                parent_id: None,
+                context: None,
+                estimate: None,
+                notes: String::new(),
+            };
+
+            ancestors.push((level, entries.len()));
+            entries.push((todo, parent));
+            continue;
+        }
+
+        let Some((todo, _)) = entries.last_mut() else {
+            continue;
+        };
+
+        for captures in planning.captures_iter(line) {
+            let date = from_org_timestamp(&captures[2])
+                .ok_or_else(|| format!("Invalid timestamp on line {}: {}", line_number + 1, &captures[2]))?;
+            match &captures[1] {
+                "SCHEDULED" => todo.scheduled_at = Some(date),
+                "DEADLINE" => todo.due_at = Some(date),
+                _ => todo.updated_at = date,
+            }
+        }
+
+        if let Some(captures) = property.captures(line) {
+            match &captures[1] {
+                "ID" => todo.uuid = Some(captures[2].to_string()),
+                _ => todo.project = Some(captures[2].to_string()),
+            }
+        }
+    }
+
+    Ok(entries)
+}
+
+// Serializes todos as org headlines, nesting subtasks under their parent todo
+pub fn serialize(todos: &[Todo]) -> String {
+    let ids: HashSet<i32> = todos.iter().map(|todo| todo.id).collect();
+    let mut children: HashMap<Option<i32>, Vec<&Todo>> = HashMap::new();
+    for todo in todos {
+        // Todos whose parent is not exported are written as top level headlines
+        let parent = todo.parent_id.filter(|id| ids.contains(id));
+        children.entry(parent).or_default().push(todo);
+    }
+    for siblings in children.values_mut() {
+        siblings.sort_by_key(|todo| todo.id);
+    }
+
+    let mut output = String::new();
+    let mut written = HashSet::new();
+    write_headlines(&mut output, &children, None, 1, &mut written);
+    output
+}
+
+fn write_headlines(
+    output: &mut String,
+    children: &HashMap<Option<i32>, Vec<&Todo>>,
+    parent: Option<i32>,
+    level: usize,
+    written: &mut HashSet<i32>,
+) {
+    let Some(siblings) = children.get(&parent) else {
+        return;
+    };
+
+    for todo in siblings {
+        if !written.insert(todo.id) {
+            continue;
+        }
+
+        let indent = " ".repeat(level + 1);
+        output.push_str(&"*".repeat(level));
+        output.push_str(if todo.done { " DONE " } else { " TODO " });
+        if let Some(cookie) = priority_to_cookie(todo.priority) {
+            output.push_str(&format!("[#{}] ", cookie));
+        }
+        output.push_str(&todo.title);
+        if !todo.tags.is_empty() {
+            output.push_str(&format!(" :{}:", todo.tags.join(":")));
+        }
+        output.push('\n');
+
+        let mut planning = Vec::new();
+        if todo.done {
+            if let Some(closed) = to_org_timestamp(&todo.updated_at, true) {
+                planning.push(format!("CLOSED: [{}]", closed));
+            }
+        }
+        if let Some(deadline) = todo.due_at.as_deref().and_then(|date| to_org_timestamp(date, false)) {
+            planning.push(format!("DEADLINE: <{}>", deadline));
+        }
+        if let Some(scheduled) = todo.scheduled_at.as_deref().and_then(|date| to_org_timestamp(date, false)) {
+            planning.push(format!("SCHEDULED: <{}>", scheduled));
+        }
+        if !planning.is_empty() {
+            output.push_str(&format!("{}{}\n", indent, planning.join(" ")));
+        }
+
+        if todo.uuid.is_some() || todo.project.is_some() {
+            output.push_str(&format!("{}:PROPERTIES:\n", indent));
+            if let Some(uuid) = &todo.uuid {
+                output.push_str(&format!("{}:ID: {}\n", indent, uuid));
+            }
+            if let Some(project) = &todo.project {
+                output.push_str(&format!("{}:CATEGORY: {}\n", indent, project));
+            }
+            output.push_str(&format!("{}:END:\n", indent));
+        }
+
+        write_headlines(output, children, Some(todo.id), level + 1, written);
+    }
+}
+
+// Org priority cookies go from [#A] (highest) to [#C], tc priorities grow with importance
+fn priority_from_cookie(cookie: &str) -> i32 {
+    match cookie.to_ascii_uppercase().as_str() {
+        "A" => 3,
+        "B" => 2,
+        "C" => 1,
+        _ => 0,
+    }
+}
+
+fn priority_to_cookie(priority: i32) -> Option<char> {
+    match priority {
+        p if p >= 3 => Some('A'),
+        2 => Some('B'),
+        1 => Some('C'),
+        _ => None,
+    }
+}
+
+// Accepts the inside of an org timestamp, e.g. `2024-01-10 Wed`, `2024-01-10 Wed 10:00 +1w`
+fn from_org_timestamp(timestamp: &str) -> Option<String> {
+    let timestamp_regex = Regex::new(r"^\s*(\d{4}-\d{2}-\d{2})(?:\s+[^\s\d+.-]+)?(?:\s+(\d{1,2}:\d{2}))?").unwrap();
+    let captures = timestamp_regex.captures(timestamp)?;
+
+    let date = NaiveDate::parse_from_str(&captures[1], "%Y-%m-%d").ok()?;
+    let time = match captures.get(2) {
+        Some(time) => NaiveTime::parse_from_str(time.as_str(), "%H:%M").ok()?,
+        None => NaiveTime::MIN,
+    };
+
+    Some(dates::to_timestamp(NaiveDateTime::new(date, time)))
+}
+
+fn to_org_timestamp(date: &str, with_time: bool) -> Option<String> {
+    let date = dates::parse_timestamp(date)?;
+    if with_time || date.hour() != 0 || date.minute() != 0 {
+        Some(date.format("%Y-%m-%d %a %H:%M").to_string())
+    } else {
+        Some(date.format("%Y-%m-%d %a").to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const AGENDA: &str = "#+TITLE: Agenda
+
+* TODO [#A] Write report :work:writing:
+  DEADLINE: <2024-01-12 Fri 10:00> SCHEDULED: <2024-01-10 Wed>
+  :PROPERTIES:
+  :ID: 3b0d5f7e-7c2a-4b6e-9f1d-0e4c2a1b3c55
+  :CATEGORY: work
+  :END:
+  Some notes about the report.
+** DONE Collect numbers
+   CLOSED: [2024-01-09 Tue 18:30]
+** TODO [#C] Draft
+*** Outline
+* Groceries
+";
+
+    #[test]
+    fn parse_agenda() {
+        let entries = parse(AGENDA).unwrap();
+        assert_eq!(entries.len(), 5);
+
+        let (report, parent) = &entries[0];
+        assert_eq!(*parent, None);
+        assert_eq!(report.title, "Write report");
+        assert!(!report.done);
+        assert_eq!(report.priority, 3);
+        assert_eq!(report.tags, vec!["work".to_string(), "writing".to_string()]);
+        assert_eq!(report.uuid.as_deref(), Some("3b0d5f7e-7c2a-4b6e-9f1d-0e4c2a1b3c55"));
+        assert_eq!(report.project.as_deref(), Some("work"));
+        assert_eq!(report.due_at.as_deref().and_then(|d| to_org_timestamp(d, false)).as_deref(), Some("2024-01-12 Fri 10:00"));
+        assert_eq!(report.scheduled_at.as_deref().and_then(|d| to_org_timestamp(d, false)).as_deref(), Some("2024-01-10 Wed"));
+
+        let (numbers, parent) = &entries[1];
+        assert_eq!(*parent, Some(0));
+        assert!(numbers.done);
+        assert_eq!(to_org_timestamp(&numbers.updated_at, true).as_deref(), Some("2024-01-09 Tue 18:30"));
+
+        assert_eq!(entries[2].0.priority, 1);
+        assert_eq!(entries[2].1, Some(0));
+        assert_eq!(entries[3].0.title, "Outline");
+        assert_eq!(entries[3].1, Some(2));
+        assert_eq!(entries[4].0.title, "Groceries");
+        assert_eq!(entries[4].1, None);
+    }
+
+    #[test]
+    fn parse_invalid_timestamp() {
+        assert!(parse("* TODO Broken\n  DEADLINE: <2024-13-45 Fri>\n").is_err());
+    }
+
+    #[test]
+    fn serialize_nested_todos() {
+        let mut todos: Vec<Todo> = parse(AGENDA).unwrap().into_iter().map(|(todo, _)| todo).collect();
+        let parents = [None, Some(1), Some(1), Some(3), None];
+        for (index, todo) in todos.iter_mut().enumerate() {
+            todo.id = index as i32 + 1;
+            todo.parent_id = parents[index];
+        }
+
+        let serialized = serialize(&todos);
+        assert!(serialized.starts_with("* TODO [#A] Write report :work:writing:\n  DEADLINE: <2024-01-12 Fri 10:00> SCHEDULED: <2024-01-10 Wed>\n"));
+        assert!(serialized.contains("** DONE Collect numbers\n   CLOSED: [2024-01-09 Tue 18:30]\n"));
+        assert!(serialized.contains("*** TODO Outline\n"));
+
+        let reparsed = parse(&serialized).unwrap();
+        let parents: Vec<Option<usize>> = reparsed.iter().map(|(_, parent)| *parent).collect();
+        assert_eq!(parents, vec![None, Some(0), Some(0), Some(2), None]);
+        assert_eq!(reparsed[0].0.uuid, todos[0].uuid);
+    }
+}
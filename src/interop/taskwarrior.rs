@@ -0,0 +1,196 @@
+use chrono::{DateTime, NaiveDateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::database::Todo;
+use crate::dates;
+
+// Taskwarrior stores every date as UTC in its compact ISO 8601 form, e.g. 20240131T093000Z
+const TASKWARRIOR_DATE_FORMAT: &str = "%Y%m%dT%H%M%SZ";
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct Task {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    uuid: Option<String>,
+    description: String,
+    status: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    entry: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    modified: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    end: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    due: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    scheduled: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    priority: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    project: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    tags: Vec<String>,
+}
+
+// Parses the output of `task export`. Deleted tasks are skipped.
+pub fn parse(input: &str) -> Result<Vec<Todo>, String> {
+    let input = input.trim();
+
+    // `task export` prints a JSON array, older versions print one task per line
+    let tasks: Vec<Task> = if input.starts_with('[') {
+        serde_json::from_str(input).map_err(|e| format!("Invalid Taskwarrior export: {}", e))?
+    } else {
+        input
+            .lines()
+            .map(|line| line.trim().trim_end_matches(','))
+            .filter(|line| !line.is_empty())
+            .map(|line| serde_json::from_str(line).map_err(|e| format!("Invalid Taskwarrior task: {}", e)))
+            .collect::<Result<_, _>>()?
+    };
+
+    let now = dates::now_timestamp();
+    let todos = tasks
+        .into_iter()
+        .filter(|task| task.status != "deleted")
+        .map(|task| {
+            let created_at = task.entry.as_deref().and_then(from_taskwarrior_date).unwrap_or_else(|| now.clone());
+            let updated_at = task.end.as_deref()
+                .or(task.modified.as_deref())
+                .and_then(from_taskwarrior_date)
+                .unwrap_or_else(|| created_at.clone());
+
+            Todo {
+                id: 0,
+                title: task.description,
+                done: task.status == "completed",
+                priority: priority_from_taskwarrior(task.priority.as_deref()),
+                created_at,
+                updated_at,
+                due_at: task.due.as_deref().and_then(from_taskwarrior_date),
+                project: task.project,
+                tags: task.tags,
+                uuid: task.uuid,
+                scheduled_at: task.scheduled.as_deref().and_then(from_taskwarrior_date),
+                parent_id: None,
+                context: None,
+                estimate: None,
+                notes: String::new(),
+            }
+        })
+        .collect();
+
+    Ok(todos)
+}
+
+// Serializes todos in the format accepted by `task import`.
+pub fn serialize(todos: &[Todo]) -> Result<String, String> {
+    let tasks: Vec<Task> = todos
+        .iter()
+        .map(|todo| Task {
+            uuid: todo.uuid.clone(),
+            description: todo.title.clone(),
+            status: if todo.done { "completed" } else { "pending" }.to_string(),
+            entry: to_taskwarrior_date(&todo.created_at),
+            modified: to_taskwarrior_date(&todo.updated_at),
+            end: if todo.done { to_taskwarrior_date(&todo.updated_at) } else { None },
+            due: todo.due_at.as_deref().and_then(to_taskwarrior_date),
+            scheduled: todo.scheduled_at.as_deref().and_then(to_taskwarrior_date),
+            priority: priority_to_taskwarrior(todo.priority),
+            project: todo.project.clone(),
+            tags: todo.tags.clone(),
+        })
+        .collect();
+
+    serde_json::to_string_pretty(&tasks).map_err(|e| format!("Failed to serialize todos: {}", e))
+}
+
+// tc priorities are open ended integers, H/M/L map onto 3/2/1
+fn priority_from_taskwarrior(priority: Option<&str>) -> i32 {
+    match priority {
+        Some("H") => 3,
+        Some("M") => 2,
+        Some("L") => 1,
+        _ => 0,
+    }
+}
+
+fn priority_to_taskwarrior(priority: i32) -> Option<String> {
+    match priority {
+        p if p >= 3 => Some("H".to_string()),
+        2 => Some("M".to_string()),
+        1 => Some("L".to_string()),
+        _ => None,
+    }
+}
+
+fn from_taskwarrior_date(date: &str) -> Option<String> {
+    NaiveDateTime::parse_from_str(date, TASKWARRIOR_DATE_FORMAT)
+        .ok()
+        .map(|date| dates::format_timestamp(&date.and_utc()))
+}
+
+fn to_taskwarrior_date(date: &str) -> Option<String> {
+    DateTime::parse_from_rfc3339(date)
+        .ok()
+        .map(|date| date.with_timezone(&Utc).format(TASKWARRIOR_DATE_FORMAT).to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXPORT: &str = r#"[
+        {"id":1,"description":"Write report","entry":"20240102T030405Z","modified":"20240103T000000Z","status":"pending","uuid":"8a1e6f1c-0a4b-4b7e-9d3c-3f0e6b2c1d11","priority":"H","project":"work","tags":["writing","q1"],"due":"20240110T120000Z","urgency":9.1},
+        {"id":0,"description":"Buy milk","entry":"20240101T000000Z","end":"20240101T100000Z","status":"completed","uuid":"0c5c2a4e-1b0a-4a39-a9c1-8f8a4a3c2b22"},
+        {"id":0,"description":"Old task","entry":"20240101T000000Z","status":"deleted","uuid":"6d2f0b0e-3f43-4b7a-8a6e-8b9e0c1d2e33"}
+    ]"#;
+
+    #[test]
+    fn parse_export() {
+        let todos = parse(EXPORT).unwrap();
+        assert_eq!(todos.len(), 2);
+
+        let report = &todos[0];
+        assert_eq!(report.title, "Write report");
+        assert!(!report.done);
+        assert_eq!(report.priority, 3);
+        assert_eq!(report.project.as_deref(), Some("work"));
+        assert_eq!(report.tags, vec!["writing".to_string(), "q1".to_string()]);
+        assert_eq!(report.uuid.as_deref(), Some("8a1e6f1c-0a4b-4b7e-9d3c-3f0e6b2c1d11"));
+        assert_eq!(to_taskwarrior_date(&report.created_at).as_deref(), Some("20240102T030405Z"));
+        assert_eq!(report.due_at.as_deref().and_then(to_taskwarrior_date).as_deref(), Some("20240110T120000Z"));
+
+        let milk = &todos[1];
+        assert!(milk.done);
+        assert_eq!(milk.priority, 0);
+        assert_eq!(to_taskwarrior_date(&milk.updated_at).as_deref(), Some("20240101T100000Z"));
+    }
+
+    #[test]
+    fn parse_line_per_task_export() {
+        let input = r#"{"description":"First","status":"pending"}
+{"description":"Second","status":"waiting"}"#;
+        let todos = parse(input).unwrap();
+        assert_eq!(todos.len(), 2);
+        assert_eq!(todos[1].title, "Second");
+        assert!(!todos[1].done);
+    }
+
+    #[test]
+    fn parse_invalid_export() {
+        assert!(parse("[{\"status\":\"pending\"}]").is_err());
+    }
+
+    #[test]
+    fn serialize_round_trip() {
+        let todos = parse(EXPORT).unwrap();
+        let serialized = serialize(&todos).unwrap();
+        let tasks: Vec<Task> = serde_json::from_str(&serialized).unwrap();
+
+        assert_eq!(tasks[0].priority.as_deref(), Some("H"));
+        assert_eq!(tasks[0].status, "pending");
+        assert_eq!(tasks[0].end, None);
+        assert_eq!(tasks[1].status, "completed");
+        assert_eq!(tasks[1].end.as_deref(), Some("20240101T100000Z"));
+        assert_eq!(parse(&serialized).unwrap()[0].title, todos[0].title);
+    }
+}
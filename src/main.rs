@@ -1,18 +1,52 @@
-use crate::settings::{Mode, Settings};
+use clap::Parser;
+
+use crate::command::{Cli, Command};
+use crate::settings::{Config, Mode, Settings};
 
 mod database;
+mod dates;
+mod interop;
 mod utils;
 mod operations;
+mod quickadd;
+mod bulkedit;
+mod stats;
+mod standup;
 mod command;
 mod settings;
+mod profiles;
 mod shell;
 mod server;
 
 fn main() {
-    let settings = Settings::new(); 
+    // Completion scripts run tc to get the candidates, nothing else may be printed before
+    shell::completion::complete();
+
+    let cli = Cli::parse();
+    let config = Config::load(&cli.set);
+    for warning in config.iter().flat_map(|config| config.warnings()) {
+        utils::log(warning);
+    }
+
+    let settings = match config.and_then(|config| config.settings()) {
+        Ok(settings) => settings,
+        // Settings and profiles are managed without the settings, so a broken config can be fixed with tc config
+        Err(e) if matches!(cli.command, Some(Command::Config { .. }) | Some(Command::Profile { .. })) => {
+            utils::log(&e);
+            Settings::defaults()
+        }
+        Err(e) => {
+            utils::log(&e);
+            std::process::exit(1);
+        }
+    };
+
+    if !settings.colors.enabled {
+        colored::control::set_override(false);
+    }
 
     match settings.mode {
-        Mode::Shell => shell::entry::start(),
-        Mode::Server => server::entry::start(),
+        Mode::Shell => shell::entry::start(cli, &settings),
+        Mode::Server => server::entry::start(&settings),
     }
 }
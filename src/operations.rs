@@ -1,11 +1,38 @@
-use crate::database::{Todo, TodoDatabase};
+use chrono::NaiveDateTime;
 
-pub fn add_todo(tdb: &TodoDatabase, todo: &str, priority: Option<i32>) {
-    tdb.add_todo(todo, priority).expect("Failed to add todo");
+use crate::database::{Added, BackgroundTimer, NewTodo, Session, Todo, TodoDatabase, Transition};
+use crate::settings::DuplicatePolicy;
+
+pub fn add_new_todo(tdb: &TodoDatabase, todo: &NewTodo, policy: DuplicatePolicy) -> Added {
+    tdb.add_new_todo(todo, policy).expect("Failed to add todo")
+}
+
+pub fn update_todo(tdb: &TodoDatabase, todo_id: i32, todo: &str) -> bool {
+    tdb.update_todo(todo_id, todo).expect("Failed to update todo")
+}
+
+pub fn set_notes(tdb: &TodoDatabase, todo_id: i32, notes: &str) -> bool {
+    tdb.set_notes(todo_id, notes).expect("Failed to save notes")
+}
+
+pub fn set_due(tdb: &TodoDatabase, todo_id: i32, due_at: Option<&str>) -> bool {
+    tdb.set_due(todo_id, due_at).expect("Failed to set due date")
+}
+
+pub fn mark_reviewed(tdb: &TodoDatabase, todo_id: i32) {
+    tdb.mark_reviewed(todo_id).expect("Failed to mark todo as reviewed");
 }
 
-pub fn update_todo(tdb: &TodoDatabase, todo_id: i32, todo: &str) {
-    tdb.update_todo(todo_id, todo).expect("Failed to update todo");
+pub fn list_stale_todos(tdb: &TodoDatabase, since: &str) -> Vec<Todo> {
+    tdb.list_stale_todos(since).expect("Failed to list todos")
+}
+
+pub fn update_priority(tdb: &TodoDatabase, todo_id: i32, priority: i32) -> bool {
+    tdb.update_priority(todo_id, priority).expect("Failed to update priority")
+}
+
+pub fn get_todo(tdb: &TodoDatabase, todo_id: i32) -> Option<Todo> {
+    tdb.get_todo(todo_id).expect("Failed to get todo")
 }
 
 pub fn list_todos(tdb: &TodoDatabase, include_all: bool) -> Vec<Todo> {
@@ -13,25 +40,72 @@ pub fn list_todos(tdb: &TodoDatabase, include_all: bool) -> Vec<Todo> {
 }
 
 pub fn find_todos(tdb: &TodoDatabase, keyword: &str, include_all: bool) -> Vec<Todo> {
-    return tdb.find_todos(keyword, include_all).expect("Failed to find todos");
+    tdb.find_todos(keyword, include_all).expect("Failed to find todos")
 }
 
-pub fn mark_todo_as_done(tdb: &TodoDatabase, ids: &Vec<i32>) -> bool {
-    tdb.mark_as_done(ids).is_ok()
+// The ids of the todos not found, none when the todos couldn't be changed
+pub fn mark_todo_as_done(tdb: &TodoDatabase, ids: &[i32]) -> Option<Vec<i32>> {
+    tdb.mark_as_done(ids).ok()
 }
 
-pub fn mark_todo_as_undone(tdb: &TodoDatabase, ids: &Vec<i32>) -> bool {
-    tdb.mark_as_undone(ids).is_ok()
+pub fn mark_todo_as_undone(tdb: &TodoDatabase, ids: &[i32]) -> Option<Vec<i32>> {
+    tdb.mark_as_undone(ids).ok()
 }
 
-pub fn remove_todos_by_ids(tdb: &TodoDatabase, ids: &Vec<i32>) -> bool {
-    tdb.remove_todos_by_ids(ids).is_ok()
+pub fn remove_todos_by_ids(tdb: &TodoDatabase, ids: &[i32]) -> Option<Vec<i32>> {
+    tdb.remove_todos_by_ids(ids).ok()
 }
 
-pub fn remove_todos_until_date(tdb: &TodoDatabase, date: &str) -> bool {
+pub fn remove_todos_until_date(tdb: &TodoDatabase, date: NaiveDateTime) -> bool {
     tdb.remove_todos_until_date(date).is_ok()
 }
 
+// The ids of the todos changed since they were edited, none when the edits couldn't be applied
+pub fn apply_edits(tdb: &TodoDatabase, updated: &[Todo], added: &[Todo], removed: &[Todo]) -> Option<Vec<i32>> {
+    tdb.apply_edits(updated, added, removed).ok()
+}
+
 pub fn reset_todo(tdb: &TodoDatabase) -> bool {
     tdb.reset().is_ok()
 }
+
+// Nothing is imported when any of the todos fails
+pub fn import_todos(tdb: &TodoDatabase, entries: &[(Todo, Option<usize>)]) -> Result<Vec<i32>, String> {
+    tdb.import_todos(entries).map_err(|e| e.to_string())
+}
+
+pub fn set_uuid(tdb: &TodoDatabase, todo_id: i32, uuid: &str) {
+    tdb.set_uuid(todo_id, uuid).expect("Failed to set uuid");
+}
+
+pub fn list_transitions(tdb: &TodoDatabase, todo_id: i32) -> Vec<Transition> {
+    tdb.list_transitions(todo_id).expect("Failed to list transitions")
+}
+
+pub fn add_session(tdb: &TodoDatabase, todo_id: i32, started_at: &str, ended_at: &str, duration: i64, completed: bool) {
+    tdb.add_session(todo_id, started_at, ended_at, duration, completed).expect("Failed to add session");
+}
+
+pub fn list_sessions(tdb: &TodoDatabase, todo_id: i32) -> Vec<Session> {
+    tdb.list_sessions(todo_id).expect("Failed to list sessions")
+}
+
+pub fn list_sessions_between(tdb: &TodoDatabase, from: &str, until: &str) -> Vec<Session> {
+    tdb.list_sessions_between(from, until).expect("Failed to list sessions")
+}
+
+pub fn add_timer(tdb: &TodoDatabase, todo_id: Option<i32>, started_at: &str, ends_at: &str) -> i32 {
+    tdb.add_timer(todo_id, started_at, ends_at).expect("Failed to add timer")
+}
+
+pub fn get_timer(tdb: &TodoDatabase, timer_id: i32) -> Option<BackgroundTimer> {
+    tdb.get_timer(timer_id).expect("Failed to get timer")
+}
+
+pub fn list_running_timers(tdb: &TodoDatabase) -> Vec<BackgroundTimer> {
+    tdb.list_running_timers().expect("Failed to list timers")
+}
+
+pub fn finish_timer(tdb: &TodoDatabase, timer_id: i32, status: &str) -> bool {
+    tdb.finish_timer(timer_id, status).expect("Failed to update timer")
+}
@@ -0,0 +1,174 @@
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use regex::Regex;
+
+use crate::settings::DatabaseSettings;
+
+// Profile using the database tc always used, ~/.tc/todo.db
+pub const DEFAULT_PROFILE: &str = "default";
+
+// Database of a directory, its todos are kept apart from the global ones
+pub const PROJECT_DATABASE: &str = ".tc.db";
+// Marks a directory as a project of the global database, the file holds the project name
+pub const PROJECT_MARKER: &str = ".tc";
+
+#[derive(Debug, PartialEq)]
+pub enum ProjectMarker {
+    Database(PathBuf),
+    Project(String),
+}
+
+// Where the todos are read from, optionally limited to the todos of one project
+#[derive(Debug, PartialEq)]
+pub struct Location {
+    pub path: PathBuf,
+    pub project: Option<String>,
+}
+
+// ~/.tc, where the databases and the history are kept
+pub fn data_dir() -> Option<PathBuf> {
+    env::var("HOME").ok().filter(|home| !home.is_empty()).map(|home| PathBuf::from(home).join(".tc"))
+}
+
+pub fn profile_path(name: &str) -> Result<PathBuf, String> {
+    let valid_name = Regex::new(r"^[A-Za-z0-9_-]+$").unwrap();
+    if !valid_name.is_match(name) {
+        return Err(format!("Invalid profile name {}, use letters, digits, - and _", name));
+    }
+
+    let data_dir = data_dir().ok_or("HOME is not set, pass --db or set TC_DB to choose the database")?;
+    if name == DEFAULT_PROFILE {
+        Ok(data_dir.join("todo.db"))
+    } else {
+        Ok(data_dir.join("profiles").join(format!("{}.db", name)))
+    }
+}
+
+// The default profile followed by the created ones, by name
+pub fn list_profiles() -> Vec<String> {
+    let mut profiles: Vec<String> = data_dir()
+        .and_then(|dir| fs::read_dir(dir.join("profiles")).ok())
+        .into_iter()
+        .flatten()
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|extension| extension == "db"))
+        .filter_map(|path| path.file_stem().map(|name| name.to_string_lossy().to_string()))
+        .collect();
+    profiles.sort();
+    profiles.insert(0, DEFAULT_PROFILE.to_string());
+    profiles
+}
+
+// Looks for a `.tc.db` or a `.tc` marker file in the directory and its parents, the nearest one wins.
+// `~/.tc` is a directory holding the global databases, not a marker.
+pub fn find_project(dir: &Path) -> Option<ProjectMarker> {
+    for dir in dir.ancestors() {
+        let database = dir.join(PROJECT_DATABASE);
+        if database.is_file() {
+            return Some(ProjectMarker::Database(database));
+        }
+
+        let marker = dir.join(PROJECT_MARKER);
+        if marker.is_file() {
+            let content = fs::read_to_string(&marker).unwrap_or_default();
+            let name = match content.lines().map(|line| line.trim()).find(|line| !line.is_empty()) {
+                Some(name) => name.to_string(),
+                None => dir.file_name().map_or("project".to_string(), |name| name.to_string_lossy().to_string()),
+            };
+            return Some(ProjectMarker::Project(name));
+        }
+    }
+    None
+}
+
+// Picks the database from, in order, the --db and --profile flags, the project of the current directory,
+// the database.path setting (TC_DB), the database.profile setting (TC_PROFILE) and finally the default profile
+pub fn locate_database(
+    db: Option<&str>,
+    profile: Option<&str>,
+    project: Option<ProjectMarker>,
+    settings: &DatabaseSettings,
+) -> Result<Location, String> {
+    let global = |path: PathBuf| Location { path, project: None };
+
+    if let Some(path) = db {
+        return Ok(global(PathBuf::from(path)));
+    }
+    if let Some(name) = profile {
+        return existing_profile_path(name).map(global);
+    }
+
+    let global_path = match (&settings.path, &settings.profile) {
+        (Some(path), _) => Ok(PathBuf::from(path)),
+        (None, Some(name)) => existing_profile_path(name),
+        (None, None) => profile_path(DEFAULT_PROFILE),
+    };
+    match project {
+        Some(ProjectMarker::Database(path)) => Ok(global(path)),
+        Some(ProjectMarker::Project(name)) => global_path.map(|path| Location { path, project: Some(name) }),
+        None => global_path.map(global),
+    }
+}
+
+// Profiles are created with `tc profile create`, a typo in the name shouldn't silently start an empty database
+fn existing_profile_path(name: &str) -> Result<PathBuf, String> {
+    let path = profile_path(name)?;
+    if name != DEFAULT_PROFILE && !path.exists() {
+        return Err(format!("Profile {} does not exist, create it with tc profile create {}", name, name));
+    }
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn path(location: Result<Location, String>) -> PathBuf {
+        location.unwrap().path
+    }
+
+    #[test]
+    fn database_precedence() {
+        let settings = DatabaseSettings { path: Some("/tmp/settings.db".to_string()), profile: Some("work".to_string()) };
+        let project = || Some(ProjectMarker::Database(PathBuf::from("/src/app/.tc.db")));
+
+        assert_eq!(path(locate_database(Some("/tmp/flag.db"), Some("work"), project(), &settings)), PathBuf::from("/tmp/flag.db"));
+        assert_eq!(path(locate_database(None, None, project(), &settings)), PathBuf::from("/src/app/.tc.db"));
+        assert_eq!(path(locate_database(None, None, None, &settings)), PathBuf::from("/tmp/settings.db"));
+        assert!(locate_database(None, Some("no-such-profile"), None, &settings).is_err());
+        assert!(locate_database(None, Some("../escape"), None, &settings).is_err());
+
+        let default = DatabaseSettings::default();
+        assert_eq!(path(locate_database(None, None, None, &default)), profile_path(DEFAULT_PROFILE).unwrap());
+        assert_eq!(path(locate_database(None, Some(DEFAULT_PROFILE), None, &default)), data_dir().unwrap().join("todo.db"));
+
+        let location = locate_database(None, None, Some(ProjectMarker::Project("app".to_string())), &settings).unwrap();
+        assert_eq!(location, Location { path: PathBuf::from("/tmp/settings.db"), project: Some("app".to_string()) });
+    }
+
+    #[test]
+    fn find_nearest_project() {
+        let root = env::temp_dir().join(format!("tc_project_{}", std::process::id()));
+        let nested = root.join("app").join("src");
+        fs::create_dir_all(&nested).unwrap();
+        assert_eq!(find_project(&nested), None);
+
+        fs::write(root.join(PROJECT_MARKER), "\nwebsite\n").unwrap();
+        assert_eq!(find_project(&nested), Some(ProjectMarker::Project("website".to_string())));
+
+        fs::write(root.join("app").join(PROJECT_MARKER), "").unwrap();
+        assert_eq!(find_project(&nested), Some(ProjectMarker::Project("app".to_string())));
+
+        fs::write(root.join("app").join(PROJECT_DATABASE), "").unwrap();
+        assert_eq!(find_project(&nested), Some(ProjectMarker::Database(root.join("app").join(PROJECT_DATABASE))));
+
+        // A directory named .tc, like ~/.tc, is not a marker
+        fs::create_dir_all(nested.join(PROJECT_MARKER)).unwrap();
+        assert_eq!(find_project(&nested), Some(ProjectMarker::Database(root.join("app").join(PROJECT_DATABASE))));
+
+        fs::remove_dir_all(root).unwrap();
+    }
+}
@@ -0,0 +1,117 @@
+use chrono::NaiveDateTime;
+use regex::Regex;
+
+use crate::database::NewTodo;
+use crate::dates;
+use crate::utils::format_duration;
+
+// Pulls the inline tokens out of the text typed after `tc a`, the remaining words make the title:
+// `!3` or `p:3` priority, `due:fri` due date, `#tag` tags, `@context` context and `~30m` estimate.
+// Tokens which don't parse, e.g. `due:someday`, are kept in the title.
+pub fn parse(text: &str, now: NaiveDateTime) -> NewTodo {
+    let priority_regex = Regex::new(r"^(?:!|p:)(\d+)$").unwrap();
+    // Names start with a letter so issue numbers like #12 stay in the title
+    let name_regex = Regex::new(r"^[#@]([A-Za-z][\w-]*)$").unwrap();
+
+    let mut todo = NewTodo::default();
+    let mut title = Vec::new();
+    for word in text.split_whitespace() {
+        if let Some(captures) = priority_regex.captures(word) {
+            if let Ok(priority) = captures[1].parse() {
+                todo.priority = Some(priority);
+                continue;
+            }
+        }
+        if let Some(date) = word.strip_prefix("due:").and_then(|date| dates::parse(date, now)) {
+            todo.due_at = Some(dates::to_timestamp(date.start()));
+            continue;
+        }
+        if let Some(captures) = name_regex.captures(word) {
+            let name = captures[1].to_string();
+            if word.starts_with('#') {
+                if !todo.tags.contains(&name) {
+                    todo.tags.push(name);
+                }
+                continue;
+            }
+            // Only one context, another @word stays in the title
+            if todo.context.is_none() {
+                todo.context = Some(name);
+                continue;
+            }
+        }
+        if let Some(estimate) = word.strip_prefix('~').and_then(parse_estimate) {
+            todo.estimate = Some(estimate);
+            continue;
+        }
+        title.push(word);
+    }
+
+    todo.title = title.join(" ");
+    todo
+}
+
+// `30m`, `2h`, `1h30m` or plain minutes like `45`, in seconds
+fn parse_estimate(text: &str) -> Option<i64> {
+    let estimate_regex = Regex::new(r"^(?:(\d+)h)?(?:(\d+)m?)?$").unwrap();
+    let captures = estimate_regex.captures(text).filter(|_| !text.is_empty())?;
+    let hours: i64 = captures.get(1).map_or(Ok(0), |hours| hours.as_str().parse()).ok()?;
+    let minutes: i64 = captures.get(2).map_or(Ok(0), |minutes| minutes.as_str().parse()).ok()?;
+    Some((hours * 60 + minutes) * 60)
+}
+
+// What was parsed besides the title, e.g. `priority 3, due 2026-10-16, #home, @store, ~30m`
+pub fn describe(todo: &NewTodo) -> String {
+    let mut parts = Vec::new();
+    if let Some(priority) = todo.priority {
+        parts.push(format!("priority {}", priority));
+    }
+    if let Some(due_at) = &todo.due_at {
+        parts.push(format!("due {}", dates::format_local(due_at)));
+    }
+    parts.extend(todo.tags.iter().map(|tag| format!("#{}", tag)));
+    if let Some(context) = &todo.context {
+        parts.push(format!("@{}", context));
+    }
+    if let Some(estimate) = todo.estimate {
+        parts.push(format!("~{}", format_duration(estimate)));
+    }
+    parts.join(", ")
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+
+    use super::*;
+
+    #[test]
+    fn parse_inline_tokens() {
+        // A Wednesday
+        let today = NaiveDate::from_ymd_opt(2026, 10, 14).unwrap().and_hms_opt(9, 0, 0).unwrap();
+
+        let todo = parse("Buy milk !3 due:fri #home #errands @store ~30m", today);
+        assert_eq!(todo.title, "Buy milk");
+        assert_eq!(todo.priority, Some(3));
+        assert_eq!(todo.due_at, Some(dates::to_timestamp(NaiveDate::from_ymd_opt(2026, 10, 16).unwrap().and_hms_opt(0, 0, 0).unwrap())));
+        assert_eq!(todo.tags, vec!["home".to_string(), "errands".to_string()]);
+        assert_eq!(todo.context.as_deref(), Some("store"));
+        assert_eq!(todo.estimate, Some(30 * 60));
+        assert_eq!(describe(&todo), "priority 3, due 2026-10-16, #home, #errands, @store, ~30m");
+
+        let todo = parse("p:1 Write report due:+3d ~1h30m", today);
+        assert_eq!(todo.title, "Write report");
+        assert_eq!(describe(&todo), "priority 1, due 2026-10-17, ~1h 30m");
+        assert_eq!(todo.priority, Some(1));
+        assert_eq!(todo.estimate, Some(90 * 60));
+
+        // Anything that isn't a valid token is part of the title
+        let todo = parse("Email bob@example.com about due:someday !important ~ #", today);
+        assert_eq!(todo.title, "Email bob@example.com about due:someday !important ~ #");
+        assert_eq!(todo, NewTodo { title: todo.title.clone(), ..NewTodo::default() });
+
+        let todo = parse("Fix #12 crash #bug", today);
+        assert_eq!(todo.title, "Fix #12 crash");
+        assert_eq!(todo.tags, vec!["bug".to_string()]);
+    }
+}
@@ -1,3 +1,5 @@
-pub fn start() {
-    println!("Server mode is not implemented yet");
+use crate::settings::Settings;
+
+pub fn start(settings: &Settings) {
+    println!("Server mode is not implemented yet, it will listen on {}", settings.server.bind);
 }
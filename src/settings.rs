@@ -1,21 +1,124 @@
+use std::collections::HashMap;
 use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
 
+use clap::ValueEnum;
 use serde::Deserialize;
+use toml::{Table, Value};
+
+// Built-in defaults, the lowest layer of the configuration
+const DEFAULTS: &str = include_str!("../Settings.toml");
+
+// Project-local config file, looked up in the current directory and its parents
+pub const PROJECT_CONFIG: &str = ".tc.toml";
+
+// What `tc a` does when an open todo has the same title
+pub const DUPLICATE_POLICIES: [&str; 4] = ["complete-previous", "reject", "allow", "merge-and-bump-priority"];
+
+pub const COLORS: [&str; 8] = ["black", "red", "green", "yellow", "blue", "magenta", "cyan", "white"];
+
+#[derive(Clone, Copy, Debug)]
+enum Kind {
+    Text,
+    Integer,
+    Boolean,
+    // Comma separated in environment variables and on the command line
+    List,
+    Choice(&'static [&'static str]),
+}
+
+// Every setting with the environment variable overriding it and the kind of value it holds
+const KEYS: [(&str, &str, Kind); 16] = [
+    ("mode", "TC_MODE", Kind::Choice(&["shell", "server"])),
+    ("database.path", "TC_DB", Kind::Text),
+    ("database.profile", "TC_PROFILE", Kind::Text),
+    ("add.on_duplicate", "TC_ON_DUPLICATE", Kind::Choice(&DUPLICATE_POLICIES)),
+    ("list.sort", "TC_LIST_SORT", Kind::Choice(&["id", "date"])),
+    ("colors.enabled", "TC_COLORS", Kind::Boolean),
+    ("colors.highlight", "TC_COLORS_HIGHLIGHT", Kind::Choice(&COLORS)),
+    ("timer.work", "TC_TIMER_WORK", Kind::Integer),
+    ("timer.short_break", "TC_TIMER_SHORT_BREAK", Kind::Integer),
+    ("timer.long_break", "TC_TIMER_LONG_BREAK", Kind::Integer),
+    ("timer.cycles", "TC_TIMER_CYCLES", Kind::Integer),
+    ("review.days", "TC_REVIEW_DAYS", Kind::Integer),
+    ("server.bind", "TC_SERVER_BIND", Kind::Text),
+    ("notify.backends", "TC_NOTIFIER", Kind::List),
+    ("notify.command", "TC_NOTIFY_COMMAND", Kind::Text),
+    ("notify.file", "TC_NOTIFY_FILE", Kind::Text),
+];
 
 impl Settings {
-    pub fn new() -> Self {
-        Settings {
-            mode: match env::var("TC_MODE").unwrap_or_else(|_| "shell".to_string()).to_lowercase().as_str() {
-                "server" => Mode::Server,
-                _ => Mode::Shell,
-            }
-        }
+    pub fn new(overrides: &[String]) -> Result<Self, String> {
+        Config::load(overrides)?.settings()
+    }
+
+    // Only the built-in defaults, for the commands which have to run when the config is broken
+    pub fn defaults() -> Self {
+        Config::from_layers(&[], |_| None, &[]).and_then(|config| config.settings()).expect("Invalid default settings")
     }
 }
 
 #[derive(Debug, Deserialize)]
 pub struct Settings {
     pub mode: Mode,
+    #[serde(default)]
+    pub database: DatabaseSettings,
+    pub add: AddSettings,
+    pub list: ListSettings,
+    pub colors: ColorSettings,
+    pub timer: TimerSettings,
+    pub review: ReviewSettings,
+    pub server: ServerSettings,
+    #[serde(default)]
+    pub notify: NotifySettings,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct DatabaseSettings {
+    pub path: Option<String>,
+    pub profile: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AddSettings {
+    pub on_duplicate: DuplicatePolicy,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ListSettings {
+    pub sort: Sort,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ColorSettings {
+    pub enabled: bool,
+    pub highlight: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TimerSettings {
+    pub work: u64,
+    pub short_break: u64,
+    pub long_break: u64,
+    pub cycles: u64,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ReviewSettings {
+    pub days: u64,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ServerSettings {
+    pub bind: String,
+}
+
+#[derive(Clone, Debug, Default, Deserialize)]
+pub struct NotifySettings {
+    pub backends: Vec<String>,
+    pub command: Option<String>,
+    pub file: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -34,3 +137,365 @@ impl std::fmt::Display for Mode {
         write!(f, "{}", s)
     }
 }
+
+#[derive(Debug, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum Sort {
+    Id,
+    Date,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+pub enum DuplicatePolicy {
+    #[value(help = "Mark the open todos with the same title as done")]
+    CompletePrevious,
+    #[value(help = "Don't add the todo")]
+    Reject,
+    #[value(help = "Add the todo anyway")]
+    Allow,
+    #[value(help = "Keep the open todo, raise its priority and add the new tags")]
+    MergeAndBumpPriority,
+}
+
+// Settings merged from every layer, remembering where each value came from
+pub struct Config {
+    values: Table,
+    sources: HashMap<String, String>,
+    // Settings in the files which were ignored, e.g. misspelled ones
+    warnings: Vec<String>,
+}
+
+impl Config {
+    // `overrides` are the `key=value` settings given with --set
+    pub fn load(overrides: &[String]) -> Result<Self, String> {
+        let mut files = Vec::new();
+        for path in [user_config_path(), project_config_path()].into_iter().flatten() {
+            if path.is_file() {
+                let content = fs::read_to_string(&path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+                files.push((path.display().to_string(), content));
+            }
+        }
+
+        Self::from_layers(&files, |name| env::var(name).ok(), overrides)
+    }
+
+    // Applies the defaults, then the files in order, then the environment, then the `key=value` overrides
+    // given with --set
+    fn from_layers(files: &[(String, String)], env: impl Fn(&str) -> Option<String>, overrides: &[String]) -> Result<Self, String> {
+        let mut config = Config { values: Table::new(), sources: HashMap::new(), warnings: Vec::new() };
+
+        config.merge(DEFAULTS, "default")?;
+        for (source, content) in files {
+            config.merge(content, source)?;
+        }
+
+        for (key, name, kind) in KEYS {
+            if let Some(raw) = env(name) {
+                let value = parse_value(kind, &raw).map_err(|e| format!("Invalid value for {}: {}", name, e))?;
+                config.insert(key, value, name);
+            }
+        }
+
+        for set in overrides {
+            let (key, raw) = set.split_once('=').ok_or_else(|| format!("Invalid --set {}, expected key=value", set))?;
+            let key = key.trim();
+            let kind = kind_of(key).ok_or_else(|| format!("Unknown setting {} in --set, see tc config list", key))?;
+            let value = parse_value(kind, raw).map_err(|e| format!("Invalid value for {} in --set: {}", key, e))?;
+            config.insert(key, value, "--set");
+        }
+
+        Ok(config)
+    }
+
+    fn merge(&mut self, content: &str, source: &str) -> Result<(), String> {
+        let table = content.parse::<Table>().map_err(|e| format!("Invalid config file {}: {}", source, e))?;
+
+        let mut values = Vec::new();
+        flatten(table, "", &mut values);
+        for (key, value) in values {
+            let Some(kind) = kind_of(&key) else {
+                self.warnings.push(format!("Unknown setting {} in {} is ignored", key, source));
+                continue;
+            };
+            check_value(kind, &value).map_err(|e| format!("Invalid value for {} in {}: {}", key, source, e))?;
+            self.insert(&key, value, source);
+        }
+
+        Ok(())
+    }
+
+    fn insert(&mut self, key: &str, value: Value, source: &str) {
+        let mut table = &mut self.values;
+        let mut segments: Vec<&str> = key.split('.').collect();
+        let name = segments.pop().unwrap();
+        for segment in segments {
+            table = table
+                .entry(segment)
+                .or_insert_with(|| Value::Table(Table::new()))
+                .as_table_mut()
+                .unwrap();
+        }
+
+        table.insert(name.to_string(), value);
+        self.sources.insert(key.to_string(), source.to_string());
+    }
+
+    pub fn get(&self, key: &str) -> Option<&Value> {
+        let mut segments = key.split('.');
+        let mut value = self.values.get(segments.next()?)?;
+        for segment in segments {
+            value = value.get(segment)?;
+        }
+        Some(value)
+    }
+
+    pub fn warnings(&self) -> &[String] {
+        &self.warnings
+    }
+
+    pub fn source(&self, key: &str) -> Option<&str> {
+        self.sources.get(key).map(|source| source.as_str())
+    }
+
+    pub fn settings(&self) -> Result<Settings, String> {
+        let mut settings = Settings::deserialize(Value::Table(self.values.clone())).map_err(|e| format!("Invalid settings: {}", e))?;
+        settings.database.path = settings.database.path.map(|path| expand_home(&path));
+        settings.notify.file = settings.notify.file.map(|path| expand_home(&path));
+        Ok(settings)
+    }
+}
+
+pub fn keys() -> impl Iterator<Item = &'static str> {
+    KEYS.iter().map(|(key, _, _)| *key)
+}
+
+pub fn is_key(key: &str) -> bool {
+    kind_of(key).is_some()
+}
+
+fn kind_of(key: &str) -> Option<Kind> {
+    KEYS.iter().find(|(name, _, _)| *name == key).map(|(_, _, kind)| *kind)
+}
+
+// Formats a value the way it is passed on the command line
+pub fn format_value(value: &Value) -> String {
+    match value {
+        Value::String(text) => text.clone(),
+        Value::Array(values) => values.iter().map(format_value).collect::<Vec<String>>().join(","),
+        value => value.to_string(),
+    }
+}
+
+fn flatten(table: Table, prefix: &str, values: &mut Vec<(String, Value)>) {
+    for (name, value) in table {
+        let key = if prefix.is_empty() { name } else { format!("{}.{}", prefix, name) };
+        match value {
+            Value::Table(table) => flatten(table, &key, values),
+            value => values.push((key, value)),
+        }
+    }
+}
+
+fn parse_value(kind: Kind, raw: &str) -> Result<Value, String> {
+    let value = match kind {
+        Kind::Text => Value::String(raw.to_string()),
+        Kind::Integer => Value::Integer(raw.trim().parse::<i64>().map_err(|_| format!("expected a number, got {}", raw))?),
+        Kind::Boolean => match raw.trim().to_lowercase().as_str() {
+            "true" | "yes" | "on" | "1" => Value::Boolean(true),
+            "false" | "no" | "off" | "0" => Value::Boolean(false),
+            _ => return Err(format!("expected true or false, got {}", raw)),
+        },
+        Kind::List => Value::Array(
+            raw.split(',').map(|item| item.trim()).filter(|item| !item.is_empty()).map(|item| Value::String(item.to_string())).collect(),
+        ),
+        Kind::Choice(_) => Value::String(raw.trim().to_lowercase()),
+    };
+
+    check_value(kind, &value)?;
+    Ok(value)
+}
+
+fn check_value(kind: Kind, value: &Value) -> Result<(), String> {
+    let valid = match kind {
+        Kind::Text => value.is_str(),
+        Kind::Integer => value.as_integer().is_some_and(|number| number >= 0),
+        Kind::Boolean => value.is_bool(),
+        Kind::List => value.as_array().is_some_and(|values| values.iter().all(|value| value.is_str())),
+        Kind::Choice(choices) => value.as_str().is_some_and(|text| choices.contains(&text)),
+    };
+    if valid {
+        return Ok(());
+    }
+
+    Err(match kind {
+        Kind::Text => "expected a string".to_string(),
+        Kind::Integer => "expected a number of at least 0".to_string(),
+        Kind::Boolean => "expected true or false".to_string(),
+        Kind::List => "expected a list of strings".to_string(),
+        Kind::Choice(choices) => format!("expected one of {}", choices.join(", ")),
+    })
+}
+
+// Writes the setting to the config file, keeping the rest of the file including comments as is
+pub fn set_value(path: &Path, key: &str, raw: &str) -> Result<(), String> {
+    let kind = kind_of(key).ok_or_else(|| format!("Unknown setting {}", key))?;
+    let value = parse_value(kind, raw).map_err(|e| format!("Invalid value for {}: {}", key, e))?;
+
+    let content = if path.exists() {
+        fs::read_to_string(path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?
+    } else {
+        String::new()
+    };
+    let mut document = content
+        .parse::<toml_edit::DocumentMut>()
+        .map_err(|e| format!("Invalid config file {}: {}", path.display(), e))?;
+
+    let item = match value {
+        Value::Integer(number) => toml_edit::value(number),
+        Value::Boolean(flag) => toml_edit::value(flag),
+        Value::Array(values) => toml_edit::value(values.iter().filter_map(|value| value.as_str()).collect::<toml_edit::Array>()),
+        value => toml_edit::value(format_value(&value)),
+    };
+    match key.split_once('.') {
+        Some((section, name)) => {
+            // Indexing a missing section would create an inline table
+            if !document.contains_key(section) {
+                document[section] = toml_edit::table();
+            }
+            document[section][name] = item;
+        }
+        None => document[key] = item,
+    }
+
+    if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+        fs::create_dir_all(dir).map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+    }
+    fs::write(path, document.to_string()).map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+}
+
+// ~/.config/tc/config.toml, following XDG_CONFIG_HOME when it is set
+pub fn user_config_path() -> Option<PathBuf> {
+    let config_dir = match env::var("XDG_CONFIG_HOME") {
+        Ok(dir) if !dir.is_empty() => PathBuf::from(dir),
+        _ => PathBuf::from(env::var("HOME").ok()?).join(".config"),
+    };
+    Some(config_dir.join("tc").join("config.toml"))
+}
+
+pub fn project_config_path() -> Option<PathBuf> {
+    let current_dir = env::current_dir().ok()?;
+    current_dir.ancestors().map(|dir| dir.join(PROJECT_CONFIG)).find(|path| path.is_file())
+}
+
+fn expand_home(path: &str) -> String {
+    match (path.strip_prefix("~/"), env::var("HOME")) {
+        (Some(rest), Ok(home)) => format!("{}/{}", home, rest),
+        _ => path.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn no_env(_: &str) -> Option<String> {
+        None
+    }
+
+    #[test]
+    fn defaults() {
+        let settings = Config::from_layers(&[], no_env, &[]).unwrap().settings().unwrap();
+        assert!(matches!(settings.mode, Mode::Shell));
+        assert_eq!(settings.list.sort, Sort::Id);
+        assert_eq!(settings.timer.work, 25);
+        assert_eq!(settings.review.days, 7);
+        assert_eq!(settings.database.path, None);
+        assert!(settings.notify.backends.is_empty());
+    }
+
+    #[test]
+    fn later_layers_override_earlier_ones() {
+        let files = vec![
+            ("user".to_string(), "[timer]\nwork = 50\ncycles = 2\n[list]\nsort = \"date\"\n".to_string()),
+            ("project".to_string(), "[timer]\nwork = 45\n".to_string()),
+        ];
+        let env = |name: &str| match name {
+            "TC_TIMER_CYCLES" => Some("6".to_string()),
+            "TC_NOTIFIER" => Some("command, bell".to_string()),
+            _ => None,
+        };
+
+        let config = Config::from_layers(&files, env, &[]).unwrap();
+        let settings = config.settings().unwrap();
+        assert_eq!(settings.timer.work, 45);
+        assert_eq!(settings.timer.cycles, 6);
+        assert_eq!(settings.timer.short_break, 5);
+        assert_eq!(settings.list.sort, Sort::Date);
+        assert_eq!(settings.notify.backends, vec!["command".to_string(), "bell".to_string()]);
+
+        assert_eq!(config.source("timer.work"), Some("project"));
+        assert_eq!(config.source("timer.cycles"), Some("TC_TIMER_CYCLES"));
+        assert_eq!(config.source("list.sort"), Some("user"));
+        assert_eq!(config.source("server.bind"), Some("default"));
+        assert_eq!(config.get("notify.backends").map(format_value).as_deref(), Some("command,bell"));
+    }
+
+    #[test]
+    fn reject_invalid_settings() {
+        let file = |content: &str| vec![("user".to_string(), content.to_string())];
+
+        assert!(Config::from_layers(&file("[list]\nsort = \"priority\"\n"), no_env, &[]).is_err());
+        assert!(Config::from_layers(&file("[timer]\nwork = \"long\"\n"), no_env, &[]).is_err());
+        assert!(Config::from_layers(&file("[timer\n"), no_env, &[]).is_err());
+
+        let env = |name: &str| (name == "TC_COLORS").then(|| "maybe".to_string());
+        assert!(Config::from_layers(&[], env, &[]).is_err());
+
+        assert!(Config::from_layers(&[], no_env, &["timer.work".to_string()]).is_err());
+        assert!(Config::from_layers(&[], no_env, &["timer.snooze=5".to_string()]).is_err());
+        assert!(Config::from_layers(&[], no_env, &["timer.work=long".to_string()]).is_err());
+    }
+
+    #[test]
+    fn warn_about_unknown_settings() {
+        let files = vec![("user".to_string(), "[timer]\nsnooze = 5\nwork = 50\n".to_string())];
+
+        let config = Config::from_layers(&files, no_env, &[]).unwrap();
+        assert_eq!(config.warnings(), ["Unknown setting timer.snooze in user is ignored".to_string()]);
+        assert_eq!(config.settings().unwrap().timer.work, 50);
+    }
+
+    #[test]
+    fn command_line_overrides_everything() {
+        let files = vec![("user".to_string(), "[timer]\nwork = 50\n".to_string())];
+        let env = |name: &str| (name == "TC_TIMER_WORK").then(|| "45".to_string());
+        let overrides = vec!["timer.work=30".to_string(), "colors.enabled = off".to_string()];
+
+        let config = Config::from_layers(&files, env, &overrides).unwrap();
+        let settings = config.settings().unwrap();
+        assert_eq!(settings.timer.work, 30);
+        assert!(!settings.colors.enabled);
+        assert_eq!(config.source("timer.work"), Some("--set"));
+    }
+
+    #[test]
+    fn set_value_keeps_comments() {
+        let path = env::temp_dir().join(format!("tc_config_{}.toml", std::process::id()));
+        fs::write(&path, "# my settings\n[timer]\nwork = 50 # longer sessions\n").unwrap();
+
+        set_value(&path, "timer.cycles", "3").unwrap();
+        set_value(&path, "notify.backends", "bell,file").unwrap();
+        set_value(&path, "mode", "Server").unwrap();
+        assert!(set_value(&path, "timer.snooze", "3").is_err());
+        assert!(set_value(&path, "colors.enabled", "maybe").is_err());
+
+        let content = fs::read_to_string(&path).unwrap();
+        assert!(content.starts_with("mode = \"server\"\n# my settings\n[timer]\nwork = 50 # longer sessions\ncycles = 3\n"));
+        assert!(content.contains("[notify]\nbackends = [\"bell\", \"file\"]\n"));
+
+        let config = Config::from_layers(&[("user".to_string(), content)], no_env, &[]).unwrap();
+        assert_eq!(config.settings().unwrap().timer.cycles, 3);
+        fs::remove_file(path).unwrap();
+    }
+}
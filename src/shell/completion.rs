@@ -0,0 +1,135 @@
+use std::env;
+use std::ffi::OsString;
+use std::io::{self, Write};
+use std::path::Path;
+
+use clap::CommandFactory;
+use clap::builder::StyledStr;
+use clap_complete::env::Shells;
+use clap_complete::{CompleteEnv, CompletionCandidate, Shell};
+
+use crate::command::Cli;
+use crate::database::TodoDatabase;
+use crate::profiles::{find_project, locate_database};
+use crate::settings::Settings;
+
+// Set by the completion scripts when they ask tc for the candidates of the word being completed
+const COMPLETE_VAR: &str = "COMPLETE";
+
+fn cli() -> clap::Command {
+    Cli::command().name("tc").bin_name("tc")
+}
+
+// Answers the completion requests of the scripts printed by `tc completions`, exits when it did
+pub fn complete() {
+    CompleteEnv::with_factory(cli).var(COMPLETE_VAR).complete();
+}
+
+// The scripts call back into tc on every tab, so subcommands, flags and todo ids are always up to date
+pub fn write_registration(shell: Shell, out: &mut dyn Write) -> io::Result<()> {
+    let shells = Shells::builtins();
+    let completer = shells
+        .completer(&shell.to_string())
+        .ok_or_else(|| io::Error::new(io::ErrorKind::Unsupported, format!("Completions are not supported for {}", shell)))?;
+
+    // Run tc the same way it was run now, a relative path only works from the current directory
+    let mut program = env::args_os().next().map(std::path::PathBuf::from).unwrap_or_else(|| "tc".into());
+    if program.components().count() > 1 {
+        if let Ok(dir) = env::current_dir() {
+            program = dir.join(program);
+        }
+    }
+
+    completer.write_registration(COMPLETE_VAR, "tc", "tc", &program.to_string_lossy(), out)
+}
+
+// Writes tc.1 and a page for every subcommand, e.g. tc-a.1 for `tc a`
+pub fn write_man_pages(dir: &Path) -> io::Result<()> {
+    clap_mangen::generate_to(cli(), dir)
+}
+
+pub fn write_man_page(out: &mut dyn Write) -> io::Result<()> {
+    clap_mangen::Man::new(cli()).render(out)
+}
+
+pub fn open_todo_candidates() -> Vec<CompletionCandidate> {
+    todo_candidates(Some(false))
+}
+
+pub fn done_todo_candidates() -> Vec<CompletionCandidate> {
+    todo_candidates(Some(true))
+}
+
+pub fn all_todo_candidates() -> Vec<CompletionCandidate> {
+    todo_candidates(None)
+}
+
+// Completion has to stay quiet, any failure just means there is nothing to suggest
+fn todo_candidates(done: Option<bool>) -> Vec<CompletionCandidate> {
+    let Some(tdb) = completion_database() else {
+        return Vec::new();
+    };
+
+    tdb.list_todos(done != Some(false))
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|todo| done.is_none_or(|done| todo.done == done))
+        .map(|todo| CompletionCandidate::new(todo.id.to_string()).help(Some(StyledStr::from(todo.title))))
+        .collect()
+}
+
+// The --db, --profile and --global flags among the typed words, which are parsed as far as they go since the
+// command is still being typed
+fn database_flags(words: Vec<OsString>) -> (Option<String>, Option<String>, bool) {
+    let Ok(matches) = cli().ignore_errors(true).try_get_matches_from(words) else {
+        return (None, None, false);
+    };
+    let value = |name: &str| matches.try_get_one::<String>(name).ok().flatten().cloned();
+    let global = matches.try_get_one::<bool>("global").ok().flatten().copied().unwrap_or(false);
+    (value("db"), value("profile"), global)
+}
+
+// The database the completed command will use, following the --db, --profile and --global flags typed so far
+fn completion_database() -> Option<TodoDatabase> {
+    let settings = Settings::new(&[]).ok()?;
+    // The scripts pass the typed words after `--`, e.g. `tc -- tc show --db=work.db 1`
+    let words = env::args_os().skip_while(|arg| arg != "--").skip(1).collect();
+    let (db, profile, global) = database_flags(words);
+
+    let project = if global { None } else { env::current_dir().ok().and_then(|dir| find_project(&dir)) };
+    let location = locate_database(db.as_deref(), profile.as_deref(), project, &settings.database).ok()?;
+    // Completing shouldn't create a database
+    if !location.path.is_file() {
+        return None;
+    }
+
+    let mut tdb = TodoDatabase::with_path(&location.path.to_string_lossy());
+    if let Some(project) = &location.project {
+        tdb = tdb.with_project(project);
+    }
+    Some(tdb)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn registration_for_every_shell() {
+        for shell in [Shell::Bash, Shell::Elvish, Shell::Fish, Shell::PowerShell, Shell::Zsh] {
+            let mut script = Vec::new();
+            write_registration(shell, &mut script).unwrap();
+            assert!(String::from_utf8(script).unwrap().contains(COMPLETE_VAR));
+        }
+    }
+
+    #[test]
+    fn database_flags_of_typed_words() {
+        let words = |line: &str| line.split(' ').map(OsString::from).collect::<Vec<OsString>>();
+
+        assert_eq!(database_flags(words("tc --db=/tmp/todo.db d ")), (Some("/tmp/todo.db".to_string()), None, false));
+        assert_eq!(database_flags(words("tc show --profile=work 1")), (None, Some("work".to_string()), false));
+        assert_eq!(database_flags(words("tc -g u --profile work")), (None, Some("work".to_string()), true));
+        assert_eq!(database_flags(words("tc d --db")), (None, None, false));
+    }
+}
@@ -0,0 +1,239 @@
+use std::borrow::Cow;
+use std::env;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::os::unix::fs::OpenOptionsExt;
+use std::path::PathBuf;
+use std::process::Command;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use clap::CommandFactory;
+use colored::Colorize;
+use rustyline::completion::{Completer, Pair};
+use rustyline::highlight::Highlighter;
+use rustyline::history::FileHistory;
+use rustyline::{CompletionType, Config, Context, Editor, Helper, Hinter, Validator};
+
+use crate::command::Cli;
+use crate::database::Todo;
+use crate::profiles::data_dir;
+
+pub const PROMPT: &str = "[tc] Enter a command (type help for more commands): ";
+
+// Subcommands whose arguments are todo ids
+const TODO_ID_COMMANDS: [&str; 6] = ["d", "undone", "u", "r", "show", "note"];
+
+// Opens the text in $VISUAL or $EDITOR (vi when neither is set) and returns it as saved when the editor exits.
+// `name` ends up in the temporary file name, its extension lets the editor pick the syntax.
+pub fn edit_text(text: &str, name: &str) -> io::Result<String> {
+    let editor = env::var("VISUAL").or_else(|_| env::var("EDITOR")).unwrap_or_else(|_| "vi".to_string());
+    // Editors are often set with arguments, e.g. `code --wait`
+    let words = shlex::split(&editor)
+        .filter(|words| !words.is_empty())
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, format!("Invalid editor: {}", editor)))?;
+
+    let (path, mut file) = create_temp_file(name)?;
+    file.write_all(text.as_bytes())?;
+    drop(file);
+    let status = Command::new(&words[0]).args(&words[1..]).arg(&path).status();
+    let edited = status.and_then(|status| {
+        if status.success() {
+            fs::read_to_string(&path)
+        } else {
+            Err(io::Error::other(format!("{} exited with {}", words[0], status)))
+        }
+    });
+
+    let _ = fs::remove_file(&path);
+    edited
+}
+
+// A new file only the user can read. The name isn't predictable and an existing file or symlink is never
+// followed, so other users can neither read the text nor make tc write elsewhere.
+fn create_temp_file(name: &str) -> io::Result<(PathBuf, File)> {
+    let nanos = SystemTime::now().duration_since(UNIX_EPOCH).map(|time| time.subsec_nanos()).unwrap_or_default();
+    for attempt in 0..100 {
+        let path = env::temp_dir().join(format!("tc-{}-{}-{}", std::process::id(), nanos.wrapping_add(attempt), name));
+        match OpenOptions::new().write(true).create_new(true).mode(0o600).open(&path) {
+            Ok(file) => return Ok((path, file)),
+            Err(e) if e.kind() == io::ErrorKind::AlreadyExists => continue,
+            Err(e) => return Err(e),
+        }
+    }
+    Err(io::Error::new(io::ErrorKind::AlreadyExists, format!("Failed to create a temporary file for {}", name)))
+}
+
+#[derive(Helper, Hinter, Validator)]
+pub struct TodoHelper {
+    // Subcommand names and aliases
+    commands: Vec<String>,
+    todos: Vec<Todo>,
+}
+
+impl TodoHelper {
+    fn new() -> Self {
+        let commands = Cli::command()
+            .get_subcommands()
+            .flat_map(|subcommand| {
+                std::iter::once(subcommand.get_name().to_string())
+                    .chain(subcommand.get_all_aliases().map(|alias| alias.to_string()))
+            })
+            .chain(std::iter::once("help".to_string()))
+            .collect();
+
+        TodoHelper { commands, todos: Vec::new() }
+    }
+
+    // Todos offered when completing ids, refreshed before every prompt
+    pub fn set_todos(&mut self, todos: Vec<Todo>) {
+        self.todos = todos;
+    }
+
+    fn complete_command(&self, prefix: &str) -> Vec<Pair> {
+        let mut commands: Vec<&String> = self.commands.iter().filter(|command| command.starts_with(prefix)).collect();
+        commands.sort();
+        commands
+            .into_iter()
+            .map(|command| Pair { display: command.clone(), replacement: format!("{} ", command) })
+            .collect()
+    }
+
+    fn complete_todo_id(&self, command: &str, prefix: &str) -> Vec<Pair> {
+        let prefix_lowercase = prefix.to_lowercase();
+
+        self.todos
+            .iter()
+            .filter(|todo| match command {
+                "d" => !todo.done,
+                "undone" => todo.done,
+                _ => true,
+            })
+            .filter(|todo| todo.id.to_string().starts_with(prefix) || todo.title.to_lowercase().contains(&prefix_lowercase))
+            .map(|todo| Pair {
+                display: format!("{:>4}  {}", todo.id, todo.title),
+                replacement: format!("{} ", todo.id),
+            })
+            .collect()
+    }
+}
+
+impl Completer for TodoHelper {
+    type Candidate = Pair;
+
+    fn complete(&self, line: &str, pos: usize, _ctx: &Context<'_>) -> rustyline::Result<(usize, Vec<Pair>)> {
+        let line = &line[..pos];
+        let start = line.rfind(char::is_whitespace).map_or(0, |index| index + 1);
+        let prefix = &line[start..];
+
+        let mut words = line[..start].split_whitespace();
+        let Some(command) = words.next() else {
+            return Ok((start, self.complete_command(prefix)));
+        };
+
+        // Resolve aliases such as `done` to the subcommand name
+        let command = match Cli::command().find_subcommand(command) {
+            Some(subcommand) => subcommand.get_name().to_string(),
+            None => return Ok((start, Vec::new())),
+        };
+        if !TODO_ID_COMMANDS.contains(&command.as_str()) || prefix.starts_with('-') {
+            return Ok((start, Vec::new()));
+        }
+
+        Ok((start, self.complete_todo_id(&command, prefix)))
+    }
+}
+
+impl Highlighter for TodoHelper {
+    fn highlight_prompt<'b, 's: 'b, 'p: 'b>(&'s self, prompt: &'p str, default: bool) -> Cow<'b, str> {
+        match prompt.strip_prefix("[tc] ") {
+            Some(text) if default => Cow::Owned(format!("{} {}", "[tc]".blue(), text.blue())),
+            _ => Cow::Borrowed(prompt),
+        }
+    }
+}
+
+// None when HOME is not set, the history is not kept then
+pub fn history_path() -> Option<PathBuf> {
+    data_dir().map(|dir| dir.join("history"))
+}
+
+pub fn create_editor() -> rustyline::Result<Editor<TodoHelper, FileHistory>> {
+    let config = Config::builder()
+        .max_history_size(1000)?
+        .history_ignore_dups(true)?
+        .history_ignore_space(true)
+        .auto_add_history(true)
+        .completion_type(CompletionType::List)
+        .build();
+
+    let mut editor = Editor::with_config(config)?;
+    editor.set_helper(Some(TodoHelper::new()));
+    // The history file doesn't exist on the first run
+    if let Some(path) = history_path() {
+        let _ = editor.load_history(&path);
+    }
+
+    Ok(editor)
+}
+
+#[cfg(test)]
+mod tests {
+    use rustyline::history::DefaultHistory;
+
+    use super::*;
+
+    fn todo(id: i32, title: &str, done: bool) -> Todo {
+        Todo { done, ..Todo::new(id, title) }
+    }
+
+    fn complete(helper: &TodoHelper, line: &str) -> (usize, Vec<String>) {
+        let history = DefaultHistory::new();
+        let ctx = Context::new(&history);
+        let (start, candidates) = helper.complete(line, line.len(), &ctx).unwrap();
+        (start, candidates.into_iter().map(|pair| pair.replacement).collect())
+    }
+
+    #[test]
+    fn complete_subcommands_and_aliases() {
+        let helper = TodoHelper::new();
+        let (start, candidates) = complete(&helper, "un");
+        assert_eq!(start, 0);
+        assert_eq!(candidates, vec!["undone ".to_string()]);
+
+        let (_, candidates) = complete(&helper, "l");
+        assert_eq!(candidates, vec!["l ".to_string(), "list ".to_string(), "ls ".to_string()]);
+    }
+
+    #[test]
+    fn complete_todo_ids_by_id_and_title() {
+        let mut helper = TodoHelper::new();
+        helper.set_todos(vec![todo(1, "Buy milk", false), todo(12, "Write report", false), todo(13, "Call mom", true)]);
+
+        let (start, candidates) = complete(&helper, "done 1");
+        assert_eq!(start, 5);
+        assert_eq!(candidates, vec!["1 ".to_string(), "12 ".to_string()]);
+
+        let (_, candidates) = complete(&helper, "d 1 rep");
+        assert_eq!(candidates, vec!["12 ".to_string()]);
+
+        let (_, candidates) = complete(&helper, "undone ");
+        assert_eq!(candidates, vec!["13 ".to_string()]);
+
+        let (_, candidates) = complete(&helper, "a 1");
+        assert!(candidates.is_empty());
+    }
+
+    #[test]
+    fn temp_files_are_private_and_unique() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let (path, _) = create_temp_file("note-1.md").unwrap();
+        let (other, _) = create_temp_file("note-1.md").unwrap();
+        assert_ne!(path, other);
+        assert!(path.to_string_lossy().ends_with("-note-1.md"));
+        assert_eq!(fs::metadata(&path).unwrap().permissions().mode() & 0o777, 0o600);
+
+        fs::remove_file(path).unwrap();
+        fs::remove_file(other).unwrap();
+    }
+}
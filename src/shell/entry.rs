@@ -1,106 +1,245 @@
-use clap::Parser;
+use std::env;
+use std::path::Path;
 
-use crate::command::{Cli, Command};
-use crate::database::TodoDatabase;
-use crate::shell::handlers::{handle_add, handle_done, handle_find, handle_help, handle_list, handle_remove, handle_reset, handle_timer, handle_undone, handle_update};
-use crate::utils::{log, user_input};
+use clap::{CommandFactory, Parser};
+use clap::error::ErrorKind;
+use rustyline::error::ReadlineError;
 
-pub fn start() {
-    let cli = Cli::parse();
+use crate::command::{Cli, Command, ConfigAction, ProfileAction, Report, TimerAction};
+use crate::database::TodoDatabase;
+use crate::operations::list_todos;
+use crate::shell::editor::{create_editor, history_path, PROMPT};
+use crate::shell::timer::Pomodoro;
+use crate::profiles::{find_project, locate_database, ProjectMarker};
+use crate::settings::{Settings, Sort};
+use crate::shell::handlers::{fire_elapsed_timers, handle_add, handle_background_timer, handle_completions, handle_config_get, handle_config_list, handle_config_path, handle_config_set, handle_profile_create, handle_profile_ls, handle_profile_switch, handle_done, handle_edit, handle_export, handle_find, handle_help, handle_init, handle_import, handle_list, handle_man, handle_note, handle_pomodoro, handle_remove, handle_report_time, handle_reset, handle_review, handle_show, handle_standup, handle_stats, handle_stopwatch, handle_timer, handle_timer_cancel, handle_timer_status, handle_timer_watch, handle_ui, handle_undone, handle_update};
+use crate::utils::log;
 
+pub fn start(cli: Cli, settings: &Settings) {
     match cli.command {
-        Some(command) => handle_command(command),
+        Some(command) => {
+            let project = if cli.global { None } else { env::current_dir().ok().and_then(|dir| find_project(&dir)) };
+            handle_command(command, settings, &cli.set, cli.db.as_deref(), cli.profile.as_deref(), project)
+        }
         None => log("No command provided"),
     }
 }
 
-fn handle_command(command: Command) {
-    let tdb = TodoDatabase::new();
+fn handle_command(command: Command, settings: &Settings, overrides: &[String], db: Option<&str>, profile: Option<&str>, project: Option<ProjectMarker>) {
+    let location = locate_database(db, profile, project, &settings.database);
+
+    // Settings, profiles and projects are managed without opening the database, e.g. to fix its path
+    let command = match command {
+        Command::Config { action } => return handle_config(action, overrides),
+        Command::Profile { action } => {
+            return handle_profile(action, location.as_ref().ok().map(|location| location.path.as_path()), overrides);
+        }
+        Command::Init { project } => return handle_init(project.as_deref()),
+        Command::Completions { shell } => return handle_completions(shell),
+        Command::Man { dir } => return handle_man(dir.as_deref()),
+        command => command,
+    };
+
+    let location = match location {
+        Ok(location) => location,
+        Err(e) => {
+            log(&e);
+            return;
+        }
+    };
+    let mut tdb = TodoDatabase::with_path(&location.path.to_string_lossy());
+    if let Some(project) = &location.project {
+        tdb = tdb.with_project(project);
+    }
     tdb.initialize().expect("Database is not initialized");
+    fire_elapsed_timers(&tdb, &settings.notify);
 
     match command {
         Command::Interactive {} => {
+            let mut editor = match create_editor() {
+                Ok(editor) => editor,
+                Err(e) => {
+                    log(&format!("Failed to start interactive mode: {}", e));
+                    return;
+                }
+            };
+
             loop {
-                let input = match user_input("Enter a command (type help for more commands): ") {
+                if let Some(helper) = editor.helper_mut() {
+                    helper.set_todos(list_todos(&tdb, true));
+                }
+
+                let input = match editor.readline(PROMPT) {
                     Ok(input) => input,
-                    Err(_) => {
-                        log("Failed to read input, stopping...");
+                    // Ctrl-C discards the current line, Ctrl-D ends the session
+                    Err(ReadlineError::Interrupted) => continue,
+                    Err(ReadlineError::Eof) => break,
+                    Err(e) => {
+                        log(&format!("Failed to read input: {}, stopping...", e));
                         break;
                     }
                 };
-
-                let program_name = "tc";
-                let formatted_input = format!("{} {}", program_name, input.trim());
-                let args = formatted_input.split_whitespace().collect::<Vec<_>>();
-                match Cli::try_parse_from(args) {
-                    Ok(cli) => {
-                        match cli.command {
-                            Some(command) => {
-                                handle_non_interactive_command(&tdb, command);
-                            }
-                            None => {
-                                log("Invalid command provided, stopping...");
-                                break;
-                            }
-                        }
+                if let Some(path) = history_path() {
+                    if let Err(e) = editor.save_history(&path) {
+                        log(&format!("Failed to save history: {}", e));
                     }
-                    _ => {
-                        // We can't register help as command's alias because there is a conflict with the (clap's) help command
-                        if input.trim() == "help" {
-                            handle_help()
-                        } else {
-                            log("Invalid command provided, stopping...");
-                            break;
+                }
+
+                let Some(args) = shlex::split(&input) else {
+                    log("Invalid command provided, check for unclosed quotes");
+                    continue;
+                };
+                match args.first().map(|arg| arg.as_str()) {
+                    None => continue,
+                    Some("exit") | Some("quit") => break,
+                    // We can't register help as command's alias because there is a conflict with the (clap's) help command
+                    Some("help") if args.len() == 1 => handle_help(),
+                    Some(name) => match Cli::try_parse_from(std::iter::once("tc".to_string()).chain(args.clone())) {
+                        Ok(Cli { db: None, profile: None, global: false, set, command: Some(command) }) if set.is_empty() => {
+                            handle_non_interactive_command(&tdb, command, settings, overrides)
                         }
-                    }
+                        Ok(Cli { command: Some(_), .. }) => log("--db, --profile, --global and --set can only be given when starting tc"),
+                        Ok(Cli { command: None, .. }) => log("No command provided"),
+                        Err(e) => print_parse_error(&e, name),
+                    },
                 }
 
                 println!()
             }
         }
-        _ => handle_non_interactive_command(&tdb, command),
+        _ => handle_non_interactive_command(&tdb, command, settings, overrides),
     }
 }
 
-fn handle_non_interactive_command(tdb: &TodoDatabase, command: Command) {
+// Prints clap's error along with the usage of the mistyped command, clap only includes it for some errors
+fn print_parse_error(error: &clap::Error, name: &str) {
+    let _ = error.print();
+
+    let message = error.to_string();
+    if message.contains("Usage:") || matches!(error.kind(), ErrorKind::DisplayHelp | ErrorKind::DisplayVersion) {
+        return;
+    }
+
+    let mut cli = Cli::command().bin_name("tc");
+    cli.build();
+    if let Some(subcommand) = cli.find_subcommand_mut(name) {
+        println!("{}", subcommand.render_usage().ansi());
+    }
+}
+
+fn handle_profile(action: ProfileAction, current_db: Option<&Path>, overrides: &[String]) {
+    match action {
+        ProfileAction::Ls => handle_profile_ls(current_db),
+        ProfileAction::Create { name } => handle_profile_create(&name),
+        ProfileAction::Switch { name } => handle_profile_switch(&name, overrides),
+    }
+}
+
+fn handle_config(action: ConfigAction, overrides: &[String]) {
+    match action {
+        ConfigAction::Get { key } => handle_config_get(&key, overrides),
+        ConfigAction::Set { key, value, local } => handle_config_set(&key, &value, local),
+        ConfigAction::List => handle_config_list(overrides),
+        ConfigAction::Path => handle_config_path(),
+    }
+}
+
+fn handle_non_interactive_command(tdb: &TodoDatabase, command: Command, settings: &Settings, overrides: &[String]) {
     match command {
-        Command::Add { todo, priority } => {
+        Command::Add { todo, priority, raw, on_duplicate } => {
             let todo = todo.join(" ");
-            handle_add(&tdb, &todo, priority);
+            handle_add(tdb, &todo, priority, raw, on_duplicate.unwrap_or(settings.add.on_duplicate));
         }
         Command::Update { id, todo } => {
             let todo = todo.join(" ");
-            handle_update(&tdb, id, &todo);
+            handle_update(tdb, id, &todo);
         }
         Command::List { all, date } => {
-            handle_list(&tdb, all, date)
+            handle_list(tdb, all, date || settings.list.sort == Sort::Date)
         }
         Command::Find { keyword, all, date } => {
             let joined_keyword = keyword.join(" ");
             let keyword = joined_keyword.trim();
-            handle_find(&tdb, &keyword, all, date)
+            let highlight = settings.colors.enabled.then_some(settings.colors.highlight.as_str());
+            handle_find(tdb, keyword, all, date || settings.list.sort == Sort::Date, highlight)
         }
         Command::Done { ids } => {
-            handle_done(&tdb, &ids);
+            handle_done(tdb, &ids);
         }
         Command::UNDONE { ids } => {
-            handle_undone(&tdb, &ids);
+            handle_undone(tdb, &ids);
         }
         Command::Remove { ids, date } => {
-            handle_remove(&tdb, &ids, date);
+            handle_remove(tdb, &ids, date);
         }
         Command::Reset => {
-            handle_reset(&tdb);
+            handle_reset(tdb);
+        }
+        Command::Timer { action: Some(action), .. } => match action {
+            TimerAction::Status => handle_timer_status(tdb),
+            TimerAction::Cancel { id } => handle_timer_cancel(tdb, id),
+            TimerAction::Watch { id } => handle_timer_watch(tdb, id, &settings.notify),
+        },
+        Command::Timer { action: None, minutes, pomodoro, stopwatch, work, short_break, long_break, cycles, todo_id, background } => {
+            if stopwatch {
+                handle_stopwatch(tdb, todo_id);
+            } else if pomodoro {
+                handle_pomodoro(tdb, &Pomodoro {
+                    work: work.unwrap_or(settings.timer.work),
+                    short_break: short_break.unwrap_or(settings.timer.short_break),
+                    long_break: long_break.unwrap_or(settings.timer.long_break),
+                    cycles: cycles.unwrap_or(settings.timer.cycles),
+                }, todo_id, &settings.notify);
+            } else if let Some(minutes) = minutes.filter(|_| background) {
+                handle_background_timer(tdb, minutes, todo_id);
+            } else if let Some(minutes) = minutes {
+                handle_timer(tdb, minutes, todo_id, &settings.notify);
+            }
+        }
+        Command::Show { id, json } => {
+            handle_show(tdb, id, json);
+        }
+        Command::Note { id } => {
+            handle_note(tdb, id);
+        }
+        Command::Edit { keyword, all } => {
+            handle_edit(tdb, keyword.join(" ").trim(), all);
+        }
+        Command::Stats { since, week } => {
+            handle_stats(tdb, &since, week);
+        }
+        Command::Standup { since, priority } => {
+            handle_standup(tdb, &since, priority);
+        }
+        Command::Review { days } => {
+            handle_review(tdb, days.unwrap_or(settings.review.days));
+        }
+        Command::Report { report } => match report {
+            Report::Time { week } => handle_report_time(tdb, week),
+        },
+        Command::Ui => {
+            handle_ui(tdb, settings.add.on_duplicate);
         }
-        Command::Timer { minutes } => {
-            handle_timer(minutes);
+        Command::Import { file, format } => {
+            handle_import(tdb, file.as_deref(), format);
         }
+        Command::Export { file, format } => {
+            handle_export(tdb, file.as_deref(), format);
+        }
+        Command::Config { action } => handle_config(action, overrides),
+        Command::Profile { action } => handle_profile(action, Some(Path::new(&tdb.get_db_path())), overrides),
+        Command::Init { project } => handle_init(project.as_deref()),
+        Command::Completions { shell } => handle_completions(shell),
+        Command::Man { dir } => handle_man(dir.as_deref()),
         _ => {}
     }
 }
 
 #[cfg(test)]
 mod tests {
+    use crate::command::Format;
+    use crate::settings::DuplicatePolicy;
+
     use super::*;
 
     #[test]
@@ -114,7 +253,7 @@ mod tests {
     fn parse_add() {
         let args = vec!["tc", "a", "new", "todo"];
         let cli = Cli::try_parse_from(args).unwrap();
-        assert_eq!(cli.command, Some(Command::Add { todo: vec!["new".to_string(), "todo".to_string()], priority: None }));
+        assert_eq!(cli.command, Some(Command::Add { todo: vec!["new".to_string(), "todo".to_string()], priority: None, raw: false, on_duplicate: None }));
     }
 
     #[test]
@@ -194,6 +333,13 @@ mod tests {
         assert_eq!(cli.command, Some(Command::Remove { ids: vec![1, 2], date: Some("2021-01-01".to_string()) }));
     }
 
+    #[test]
+    fn parse_remove_relative_date() {
+        let args = vec!["tc", "r", "--date", "-2w"];
+        let cli = Cli::try_parse_from(args).unwrap();
+        assert_eq!(cli.command, Some(Command::Remove { ids: vec![], date: Some("-2w".to_string()) }));
+    }
+
     #[test]
     fn parse_reset() {
         let args = vec!["tc", "rs"];
@@ -205,6 +351,234 @@ mod tests {
     fn parse_timer() {
         let args = vec!["tc", "t", "10"];
         let cli = Cli::try_parse_from(args).unwrap();
-        assert_eq!(cli.command, Some(Command::Timer { minutes: 10 }));
+        assert!(matches!(cli.command, Some(Command::Timer { minutes: Some(10), pomodoro: false, .. })));
+    }
+
+    #[test]
+    fn parse_timer_pomodoro() {
+        let args = vec!["tc", "t", "--pomodoro", "--work", "50", "--cycles", "2"];
+        let cli = Cli::try_parse_from(args).unwrap();
+        assert_eq!(cli.command, Some(Command::Timer {
+            action: None,
+            minutes: None,
+            pomodoro: true,
+            stopwatch: false,
+            work: Some(50),
+            short_break: None,
+            long_break: None,
+            cycles: Some(2),
+            todo_id: None,
+            background: false,
+        }));
+    }
+
+    #[test]
+    fn parse_timer_with_todo() {
+        let args = vec!["tc", "t", "25", "--todo", "12"];
+        let cli = Cli::try_parse_from(args).unwrap();
+        assert!(matches!(cli.command, Some(Command::Timer { minutes: Some(25), todo_id: Some(12), .. })));
+    }
+
+    #[test]
+    fn parse_timer_background() {
+        let args = vec!["tc", "t", "25", "--background"];
+        let cli = Cli::try_parse_from(args).unwrap();
+        assert!(matches!(cli.command, Some(Command::Timer { action: None, minutes: Some(25), background: true, .. })));
+
+        let args = vec!["tc", "t", "--pomodoro", "--background"];
+        assert!(Cli::try_parse_from(args).is_err());
+    }
+
+    #[test]
+    fn parse_timer_stopwatch() {
+        let args = vec!["tc", "t", "--stopwatch", "--todo", "3"];
+        let cli = Cli::try_parse_from(args).unwrap();
+        assert!(matches!(cli.command, Some(Command::Timer { minutes: None, stopwatch: true, todo_id: Some(3), .. })));
+
+        let args = vec!["tc", "t", "10", "--stopwatch"];
+        assert!(Cli::try_parse_from(args).is_err());
+    }
+
+    #[test]
+    fn parse_timer_actions() {
+        let args = vec!["tc", "t", "status"];
+        let cli = Cli::try_parse_from(args).unwrap();
+        assert!(matches!(cli.command, Some(Command::Timer { action: Some(TimerAction::Status), minutes: None, .. })));
+
+        let args = vec!["tc", "timer", "cancel", "3"];
+        let cli = Cli::try_parse_from(args).unwrap();
+        assert!(matches!(cli.command, Some(Command::Timer { action: Some(TimerAction::Cancel { id: Some(3) }), .. })));
+    }
+
+    #[test]
+    fn parse_show() {
+        let args = vec!["tc", "show", "12"];
+        let cli = Cli::try_parse_from(args).unwrap();
+        assert_eq!(cli.command, Some(Command::Show { id: 12, json: false }));
+
+        let args = vec!["tc", "show", "12", "--json"];
+        let cli = Cli::try_parse_from(args).unwrap();
+        assert_eq!(cli.command, Some(Command::Show { id: 12, json: true }));
+    }
+
+    #[test]
+    fn parse_stats() {
+        let args = vec!["tc", "stats"];
+        let cli = Cli::try_parse_from(args).unwrap();
+        assert_eq!(cli.command, Some(Command::Stats { since: "4 weeks ago".to_string(), week: false }));
+
+        let args = vec!["tc", "stats", "--since", "-3m", "-w"];
+        let cli = Cli::try_parse_from(args).unwrap();
+        assert_eq!(cli.command, Some(Command::Stats { since: "-3m".to_string(), week: true }));
+    }
+
+    #[test]
+    fn parse_standup() {
+        let args = vec!["tc", "standup"];
+        let cli = Cli::try_parse_from(args).unwrap();
+        assert_eq!(cli.command, Some(Command::Standup { since: "yesterday".to_string(), priority: 2 }));
+
+        let args = vec!["tc", "standup", "--since", "last fri", "-p", "1"];
+        let cli = Cli::try_parse_from(args).unwrap();
+        assert_eq!(cli.command, Some(Command::Standup { since: "last fri".to_string(), priority: 1 }));
+    }
+
+    #[test]
+    fn parse_review() {
+        let args = vec!["tc", "review"];
+        let cli = Cli::try_parse_from(args).unwrap();
+        assert_eq!(cli.command, Some(Command::Review { days: None }));
+
+        let args = vec!["tc", "review", "--days", "14"];
+        let cli = Cli::try_parse_from(args).unwrap();
+        assert_eq!(cli.command, Some(Command::Review { days: Some(14) }));
+    }
+
+    #[test]
+    fn parse_edit() {
+        let args = vec!["tc", "edit", "milk", "-a"];
+        let cli = Cli::try_parse_from(args).unwrap();
+        assert_eq!(cli.command, Some(Command::Edit { keyword: vec!["milk".to_string()], all: true }));
+    }
+
+    #[test]
+    fn parse_config() {
+        let args = vec!["tc", "config", "set", "timer.work", "50", "--local"];
+        let cli = Cli::try_parse_from(args).unwrap();
+        assert_eq!(cli.command, Some(Command::Config {
+            action: ConfigAction::Set { key: "timer.work".to_string(), value: "50".to_string(), local: true },
+        }));
+
+        let args = vec!["tc", "config", "ls"];
+        let cli = Cli::try_parse_from(args).unwrap();
+        assert_eq!(cli.command, Some(Command::Config { action: ConfigAction::List }));
+    }
+
+    #[test]
+    fn parse_completions_and_man() {
+        let args = vec!["tc", "completions", "zsh"];
+        let cli = Cli::try_parse_from(args).unwrap();
+        assert_eq!(cli.command, Some(Command::Completions { shell: clap_complete::Shell::Zsh }));
+        assert!(Cli::try_parse_from(vec!["tc", "completions", "tcsh"]).is_err());
+
+        let args = vec!["tc", "man", "--dir", "man"];
+        let cli = Cli::try_parse_from(args).unwrap();
+        assert_eq!(cli.command, Some(Command::Man { dir: Some("man".to_string()) }));
+    }
+
+    #[test]
+    fn parse_db_and_profile() {
+        let args = vec!["tc", "--profile", "work", "l"];
+        let cli = Cli::try_parse_from(args).unwrap();
+        assert_eq!(cli.profile.as_deref(), Some("work"));
+        assert_eq!(cli.command, Some(Command::List { all: false, date: false }));
+
+        // Global flags are accepted after the subcommand as well
+        let args = vec!["tc", "a", "Buy milk", "--db", "/tmp/todo.db"];
+        let cli = Cli::try_parse_from(args).unwrap();
+        assert_eq!(cli.db.as_deref(), Some("/tmp/todo.db"));
+
+        let args = vec!["tc", "l", "--global"];
+        let cli = Cli::try_parse_from(args).unwrap();
+        assert!(cli.global);
+
+        let args = vec!["tc", "--set", "timer.work=50", "--set", "colors.enabled=false", "l"];
+        let cli = Cli::try_parse_from(args).unwrap();
+        assert_eq!(cli.set, vec!["timer.work=50".to_string(), "colors.enabled=false".to_string()]);
+        assert!(Cli::try_parse_from(vec!["tc", "l", "--set", "list.sort=date"]).is_err());
+
+        let args = vec!["tc", "init", "--project", "website"];
+        let cli = Cli::try_parse_from(args).unwrap();
+        assert_eq!(cli.command, Some(Command::Init { project: Some("website".to_string()) }));
+
+        let args = vec!["tc", "profile", "create", "work"];
+        let cli = Cli::try_parse_from(args).unwrap();
+        assert_eq!(cli.command, Some(Command::Profile { action: ProfileAction::Create { name: "work".to_string() } }));
+    }
+
+    #[test]
+    fn parse_report_time() {
+        let args = vec!["tc", "report", "time", "--week"];
+        let cli = Cli::try_parse_from(args).unwrap();
+        assert_eq!(cli.command, Some(Command::Report { report: Report::Time { week: true } }));
+    }
+
+    #[test]
+    fn parse_timer_requires_minutes() {
+        assert!(Cli::try_parse_from(vec!["tc", "t"]).is_err());
+        assert!(Cli::try_parse_from(vec!["tc", "t", "10", "--work", "50"]).is_err());
+    }
+
+    #[test]
+    fn parse_quoted_interactive_input() {
+        let args = shlex::split(r#"a "Buy  milk" 'and eggs' \"now\" -p 2"#).unwrap();
+        let cli = Cli::try_parse_from(std::iter::once("tc".to_string()).chain(args)).unwrap();
+        assert_eq!(cli.command, Some(Command::Add {
+            todo: vec!["Buy  milk".to_string(), "and eggs".to_string(), "\"now\"".to_string()],
+            priority: Some(2),
+            raw: false,
+            on_duplicate: None,
+        }));
+
+        let args = shlex::split(r#"a --raw "Fix #12 !3""#).unwrap();
+        let cli = Cli::try_parse_from(std::iter::once("tc".to_string()).chain(args)).unwrap();
+        assert_eq!(cli.command, Some(Command::Add { todo: vec!["Fix #12 !3".to_string()], priority: None, raw: true, on_duplicate: None }));
+
+        let args = vec!["tc", "a", "Buy milk", "--on-duplicate", "merge-and-bump-priority"];
+        let cli = Cli::try_parse_from(args).unwrap();
+        assert!(matches!(cli.command, Some(Command::Add { on_duplicate: Some(DuplicatePolicy::MergeAndBumpPriority), .. })));
+    }
+
+    #[test]
+    fn parse_unclosed_quote_interactive_input() {
+        assert_eq!(shlex::split(r#"a "Buy milk"#), None);
+    }
+
+    #[test]
+    fn parse_ui() {
+        let args = vec!["tc", "ui"];
+        let cli = Cli::try_parse_from(args).unwrap();
+        assert_eq!(cli.command, Some(Command::Ui));
+    }
+
+    #[test]
+    fn parse_import() {
+        let args = vec!["tc", "import", "tasks.json", "-f", "tw"];
+        let cli = Cli::try_parse_from(args).unwrap();
+        assert_eq!(cli.command, Some(Command::Import { file: Some("tasks.json".to_string()), format: Format::Taskwarrior }));
+    }
+
+    #[test]
+    fn parse_export() {
+        let args = vec!["tc", "export"];
+        let cli = Cli::try_parse_from(args).unwrap();
+        assert_eq!(cli.command, Some(Command::Export { file: None, format: Format::Taskwarrior }));
+    }
+
+    #[test]
+    fn parse_export_org() {
+        let args = vec!["tc", "export", "todos.org", "--format", "org"];
+        let cli = Cli::try_parse_from(args).unwrap();
+        assert_eq!(cli.command, Some(Command::Export { file: Some("todos.org".to_string()), format: Format::Org }));
     }
 }
@@ -1,30 +1,88 @@
 use std::collections::BTreeMap;
+use std::env;
+use std::fs;
 use std::io;
-use std::io::Write;
-use std::process::Command;
-use std::thread::sleep;
-use std::time::{Duration, Instant};
+use std::io::Read;
+use std::path::Path;
+use std::thread;
+use std::time::Duration;
 
-use chrono::NaiveDate;
+use chrono::{DateTime, Datelike, Local, NaiveDate, NaiveTime, Utc};
 use clap::CommandFactory;
+use clap_complete::Shell;
 use colored::Colorize;
-use figlet_rs::FIGfont;
 use prettytable::{Cell, color, Row, Table};
-use regex::Regex;
-use termion::terminal_size;
 
-use crate::command::Cli;
-use crate::database::{Todo, TodoDatabase};
-use crate::operations::{add_todo, find_todos, list_todos, mark_todo_as_done, mark_todo_as_undone, remove_todos_by_ids, remove_todos_until_date, reset_todo, update_todo};
-use crate::utils::{log, user_input};
+use crate::command::{Cli, Format};
+use crate::bulkedit;
+use crate::dates;
+use crate::database::{Added, BackgroundTimer, NewTodo, Todo, TodoDatabase, TIMER_CANCELLED, TIMER_FIRED, TIMER_RUNNING};
+use crate::interop::{org, taskwarrior};
+use crate::operations::{add_new_todo, add_session, apply_edits, add_timer, find_todos, finish_timer, get_timer, get_todo, import_todos, list_running_timers, list_sessions, list_sessions_between, list_stale_todos, list_todos, list_transitions, mark_reviewed, mark_todo_as_done, mark_todo_as_undone, remove_todos_by_ids, remove_todos_until_date, reset_todo, set_due, set_notes, set_uuid, update_priority, update_todo};
+use crate::profiles;
+use crate::quickadd;
+use crate::settings::{self, Config, DuplicatePolicy, NotifySettings, COLORS};
+use crate::shell::completion;
+use crate::standup;
+use crate::stats;
+use crate::shell::editor::edit_text;
+use crate::shell::notifier::notify;
+use crate::shell::timer::{countdown, format_clock, format_lap, spawn_watcher, stopwatch, Outcome, PhaseKind, Pomodoro};
+use crate::shell::tui;
+use crate::utils::{format_duration, log, normalize, similarity, user_input};
 
-pub fn handle_add(tdb: &TodoDatabase, todo: &str, priority: Option<i32>) {
-    if todo.is_empty() {
+// How close titles have to be for a todo being added to be warned about, 1.0 is identical
+const SIMILAR_TITLE: f64 = 0.85;
+
+pub fn handle_add(tdb: &TodoDatabase, todo: &str, priority: Option<i32>, raw: bool, policy: DuplicatePolicy) {
+    let mut new_todo = if raw {
+        NewTodo { title: todo.trim().to_string(), ..NewTodo::default() }
+    } else {
+        quickadd::parse(todo, Local::now().naive_local())
+    };
+    if new_todo.title.is_empty() {
         log("Todo cannot be empty");
         return;
     }
-    add_todo(tdb, todo, priority);
-    log(&format!("Added task: {}", todo));
+    if priority.is_some() {
+        new_todo.priority = priority;
+    }
+
+    // Looked up before adding, the new todo would match itself
+    let similar = similar_todos(tdb, &new_todo.title);
+    match add_new_todo(tdb, &new_todo, policy) {
+        Added::New { completed, .. } => {
+            let details = quickadd::describe(&new_todo);
+            if details.is_empty() {
+                log(&format!("Added task: {}", new_todo.title));
+            } else {
+                log(&format!("Added task: {} ({})", new_todo.title, details));
+            }
+            for id in completed {
+                log(&format!("Marked todo {} with the same title as done", id));
+            }
+        }
+        Added::Rejected { id } => {
+            log(&format!("Todo {} has the same title, not adding it (use --on-duplicate allow to add it anyway)", id));
+        }
+        Added::Merged { id, priority } => {
+            log(&format!("Merged into todo {} with the same title, its priority is now {}", id, priority));
+        }
+    }
+
+    for todo in similar {
+        log(&format!("Similar todo {}: {}", todo.id, todo.title).yellow().to_string());
+    }
+}
+
+// Open todos whose title differs only in case and spacing, or by a few typos
+fn similar_todos(tdb: &TodoDatabase, title: &str) -> Vec<Todo> {
+    let normalized = normalize(title);
+    list_todos(tdb, false)
+        .into_iter()
+        .filter(|todo| todo.title != title)
+        .filter(|todo| similarity(&normalize(&todo.title), &normalized) >= SIMILAR_TITLE)
+        .collect()
 }
 
 pub fn handle_update(tdb: &TodoDatabase, todo_id: i32, todo: &str) {
@@ -32,8 +90,11 @@ pub fn handle_update(tdb: &TodoDatabase, todo_id: i32, todo: &str) {
         log("Todo cannot be empty");
         return;
     }
-    update_todo(tdb, todo_id, todo);
-    log(&format!("Updated task: {}", todo));
+    if update_todo(tdb, todo_id, todo) {
+        log(&format!("Updated task: {}", todo));
+    } else {
+        log(&format!("Todo {} not found", todo_id));
+    }
 }
 
 pub fn handle_list(tdb: &TodoDatabase, include_all: bool, sort_by_date: bool) {
@@ -41,17 +102,19 @@ pub fn handle_list(tdb: &TodoDatabase, include_all: bool, sort_by_date: bool) {
     sort_and_print_todos(&todos, None, sort_by_date);
 }
 
-pub fn handle_find(tdb: &TodoDatabase, keyword: &str, include_all: bool, sort_by_date: bool) {
+// `highlight` is the name of the color matched keywords are shown in, None disables highlighting
+pub fn handle_find(tdb: &TodoDatabase, keyword: &str, include_all: bool, sort_by_date: bool, highlight: Option<&str>) {
     let todos = find_todos(tdb, keyword, include_all);
-    sort_and_print_todos(&todos, Some(keyword), sort_by_date);
+    let color = highlight.and_then(|name| COLORS.iter().position(|color| *color == name)).map(|index| index as color::Color);
+    sort_and_print_todos(&todos, color.map(|color| (keyword, color)), sort_by_date);
 }
 
-fn sort_and_print_todos(todos: &Vec<Todo>, highlight_keyword: Option<&str>, sort_by_date: bool) {
+fn sort_and_print_todos(todos: &Vec<Todo>, highlight_keyword: Option<(&str, color::Color)>, sort_by_date: bool) {
     if sort_by_date {
         let mut grouped_todos: BTreeMap<NaiveDate, Vec<Todo>> = BTreeMap::new();
         for todo in todos {
-            let date = NaiveDate::parse_from_str(&todo.created_at[..10], "%Y-%m-%d").expect("Invalid date format");
-            grouped_todos.entry(date).or_insert_with(Vec::new).push(todo.clone());
+            let date = dates::local_date(&todo.created_at).expect("Invalid date format");
+            grouped_todos.entry(date).or_default().push(todo.clone());
         }
 
         for (_, group) in grouped_todos.iter_mut() {
@@ -69,12 +132,12 @@ fn sort_and_print_todos(todos: &Vec<Todo>, highlight_keyword: Option<&str>, sort
         }
     } else {
         let mut todos_sorted = todos.clone();
-        todos_sorted.sort_by(|a, b| b.id.cmp(&a.id));
+        todos_sorted.sort_by_key(|todo| std::cmp::Reverse(todo.id));
         print_todos(&todos_sorted, highlight_keyword);
     }
 }
 
-fn print_todos(todos: &Vec<Todo>, keyword: Option<&str>) {
+fn print_todos(todos: &Vec<Todo>, keyword: Option<(&str, color::Color)>) {
     let mut table = Table::new();
 
     table.add_row(Row::new(vec![
@@ -88,9 +151,9 @@ fn print_todos(todos: &Vec<Todo>, keyword: Option<&str>) {
         let mark = if todo.done { "X" } else { "" };
 
         let mut title_cell = Cell::new(&todo.title);
-        if let Some(keyword) = keyword {
+        if let Some((keyword, color)) = keyword {
             if todo.title.contains(keyword) {
-                title_cell = title_cell.with_style(prettytable::Attr::ForegroundColor(color::BLUE));
+                title_cell = title_cell.with_style(prettytable::Attr::ForegroundColor(color));
             }
         }
 
@@ -105,49 +168,61 @@ fn print_todos(todos: &Vec<Todo>, keyword: Option<&str>) {
     table.printstd();
 }
 
-pub fn handle_done(tdb: &TodoDatabase, ids: &Vec<i32>) {
+pub fn handle_done(tdb: &TodoDatabase, ids: &[i32]) {
     if ids.is_empty() {
         log("No todo ids provided");
         return;
     }
 
-    match mark_todo_as_done(tdb, ids) {
-        true => log(&format!("Marked todo {:?} as done", ids)),
-        false => {}
+    if let Some(missing) = mark_todo_as_done(tdb, ids) {
+        let marked = report_missing(ids, &missing);
+        if !marked.is_empty() {
+            log(&format!("Marked todo {:?} as done", marked));
+        }
     }
 }
 
-pub fn handle_undone(tdb: &TodoDatabase, ids: &Vec<i32>) {
+pub fn handle_undone(tdb: &TodoDatabase, ids: &[i32]) {
     if ids.is_empty() {
         log("No todo ids provided");
         return;
     }
 
-    match mark_todo_as_undone(tdb, ids) {
-        true => log(&format!("Marked todo {:?} as undone", ids)),
-        false => {}
+    if let Some(missing) = mark_todo_as_undone(tdb, ids) {
+        let marked = report_missing(ids, &missing);
+        if !marked.is_empty() {
+            log(&format!("Marked todo {:?} as undone", marked));
+        }
     }
 }
 
-pub fn handle_remove(tdb: &TodoDatabase, ids: &Vec<i32>, date: Option<String>) {
-    if !ids.is_empty() {
-        match remove_todos_by_ids(tdb, ids) {
-            true => log(&format!("Removed todo {:?}", ids)),
-            false => {}
+pub fn handle_remove(tdb: &TodoDatabase, ids: &[i32], date: Option<String>) {
+    if let Some(missing) = remove_todos_by_ids(tdb, ids) {
+        let removed = report_missing(ids, &missing);
+        if !removed.is_empty() {
+            log(&format!("Removed todo {:?}", removed));
         }
     }
 
     if let Some(d) = date {
-        let yyyy_mm_dd = Regex::new(r"^\d{4}-\d{2}-\d{2}$").unwrap();
-        match d.as_str() {
-            date if yyyy_mm_dd.is_match(date) => {
-                remove_todos_until_date(tdb, date);
+        // A date without a time includes the whole day
+        match dates::parse(&d, Local::now().naive_local()) {
+            Some(date) => {
+                remove_todos_until_date(tdb, date.end());
             }
-            _ => log("Invalid date format. Use yyyy-mm-dd"),
+            None => log(&format!("Invalid date {}, use e.g. {}", d, dates::DATE_EXAMPLES)),
         }
     }
 }
 
+// Logs the ids which weren't found, in the current project, and returns the others
+fn report_missing(ids: &[i32], missing: &[i32]) -> Vec<i32> {
+    for id in missing {
+        log(&format!("Todo {} not found", id));
+    }
+    ids.iter().filter(|id| !missing.contains(id)).copied().collect()
+}
+
 pub fn handle_reset(tdb: &TodoDatabase) {
     let input = user_input("Are you sure you want to remove all todos(yes/no)?: ").expect("Failed to read input");
     if input.trim() != "yes" {
@@ -161,82 +236,943 @@ pub fn handle_reset(tdb: &TodoDatabase) {
     }
 }
 
-pub fn handle_timer(minutes: u64) {
-    let duration = Duration::from_secs(minutes * 60);
-    let end_time = Instant::now() + duration;
+pub fn handle_timer(tdb: &TodoDatabase, minutes: u64, todo_id: Option<i32>, notify_settings: &NotifySettings) {
+    let Ok(todo) = find_linked_todo(tdb, todo_id) else {
+        return;
+    };
+
+    match track_countdown(tdb, todo.as_ref(), Duration::from_secs(minutes * 60), Vec::new()) {
+        Outcome::Completed => notify(notify_settings, "todo-cli", "Time's up! Did you finish your work?"),
+        Outcome::Finished => log("Timer finished early"),
+        Outcome::Aborted => log("Timer stopped"),
+    }
+}
+
+pub fn handle_pomodoro(tdb: &TodoDatabase, pomodoro: &Pomodoro, todo_id: Option<i32>, notify_settings: &NotifySettings) {
+    if pomodoro.cycles == 0 || pomodoro.work == 0 {
+        log("Work length and cycles must be greater than zero");
+        return;
+    }
+    let Ok(mut todo) = find_linked_todo(tdb, todo_id) else {
+        return;
+    };
+
+    let phases = pomodoro.phases();
+    let mut work_sessions = 0;
+    let mut focused_minutes = 0;
+    let mut break_minutes = 0;
+
+    for (index, phase) in phases.iter().enumerate() {
+        let duration = Duration::from_secs(phase.minutes * 60);
+        let caption = vec![phase.label(pomodoro.cycles)];
+
+        // Only work sessions count as time spent on the todo
+        let outcome = match phase.kind {
+            PhaseKind::Work => track_countdown(tdb, todo.as_ref(), duration, caption),
+            _ => track_countdown(tdb, None, duration, caption),
+        };
+        if outcome == Outcome::Aborted {
+            log("Pomodoro stopped");
+            break;
+        }
+        // The todo is done, following work sessions aren't tracked against it anymore
+        if outcome == Outcome::Finished && phase.kind == PhaseKind::Work {
+            todo = None;
+        }
+
+        match phase.kind {
+            PhaseKind::Work => {
+                work_sessions += 1;
+                focused_minutes += phase.minutes;
+            }
+            _ => break_minutes += phase.minutes,
+        }
+
+        // Alerts are shown in the background so the next phase starts right away
+        if let Some(next) = phases.get(index + 1) {
+            let message = format!("{} finished, {} starts now", phase.label(pomodoro.cycles), next.label(pomodoro.cycles));
+            let notify_settings = notify_settings.clone();
+            thread::spawn(move || notify(&notify_settings, "todo-cli", &message));
+        } else {
+            notify(notify_settings, "todo-cli", "Pomodoro finished! Well done.");
+        }
+    }
+
+    log(&format!(
+        "Pomodoro summary: {} of {} work sessions, {} minutes of focus, {} minutes of breaks",
+        work_sessions, pomodoro.cycles, focused_minutes, break_minutes
+    ));
+}
+
+pub fn handle_stopwatch(tdb: &TodoDatabase, todo_id: Option<i32>) {
+    let Ok(todo) = find_linked_todo(tdb, todo_id) else {
+        return;
+    };
+    let caption: Vec<String> = todo.iter().map(|todo| format!("#{} {}", todo.id, todo.title)).collect();
+
+    let started_at = Local::now();
+    let (outcome, elapsed, laps) = match stopwatch(&caption) {
+        Ok(result) => result,
+        Err(e) => {
+            log(&format!("Failed to run the stopwatch: {}", e));
+            return;
+        }
+    };
+    // Clear the clock before printing anything else
+    print!("\x1B[2J\x1B[H");
+
+    for index in 0..laps.len() {
+        log(&format_lap(&laps, index));
+    }
+    log(&format!("Elapsed: {}", format_clock(elapsed.as_secs())));
+
+    let spent = elapsed.as_secs() as i64;
+    let todo = match todo {
+        Some(todo) => Some(todo),
+        None if outcome != Outcome::Aborted && spent > 0 => ask_todo_to_log(tdb, spent),
+        None => None,
+    };
+    if let Some(todo) = todo {
+        record_session(tdb, &todo, started_at, elapsed, outcome);
+    }
+}
+
+// Lets the user pick the todo the stopwatch time is logged against, an empty answer skips logging
+fn ask_todo_to_log(tdb: &TodoDatabase, spent: i64) -> Option<Todo> {
+    let answer = user_input(&format!("Log {} against a todo? Enter its id or leave empty to skip: ", format_duration(spent))).ok()?;
+    if answer.is_empty() {
+        return None;
+    }
+
+    let Ok(id) = answer.parse::<i32>() else {
+        log(&format!("Invalid todo id: {}", answer));
+        return None;
+    };
+    find_linked_todo(tdb, Some(id)).ok().flatten()
+}
+
+// Looks up the todo the timer is linked to. Errors when the todo doesn't exist
+fn find_linked_todo(tdb: &TodoDatabase, todo_id: Option<i32>) -> Result<Option<Todo>, ()> {
+    let Some(id) = todo_id else {
+        return Ok(None);
+    };
+
+    match get_todo(tdb, id) {
+        Some(todo) => Ok(Some(todo)),
+        None => {
+            log(&format!("Todo {} not found", id));
+            Err(())
+        }
+    }
+}
+
+// Runs the countdown and records the time spent against the todo, if any.
+// Finishing the countdown early with `d` marks the todo as done.
+fn track_countdown(tdb: &TodoDatabase, todo: Option<&Todo>, duration: Duration, mut caption: Vec<String>) -> Outcome {
+    if let Some(todo) = todo {
+        caption.push(format!("#{} {}", todo.id, todo.title));
+    }
+
+    let started_at = Local::now();
+    let (outcome, active) = match countdown(duration, &caption) {
+        Ok(result) => result,
+        Err(e) => {
+            log(&format!("Failed to run the timer: {}", e));
+            return Outcome::Aborted;
+        }
+    };
+    // Clear the clock before printing anything else
+    print!("\x1B[2J\x1B[H");
+
+    if let Some(todo) = todo {
+        record_session(tdb, todo, started_at, active, outcome);
+    }
+
+    outcome
+}
+
+// Records the time a countdown or stopwatch ran against the todo, finishing it with `d` marks the todo as done
+fn record_session(tdb: &TodoDatabase, todo: &Todo, started_at: DateTime<Local>, active: Duration, outcome: Outcome) {
+    let spent = active.as_secs() as i64;
+    add_session(tdb, todo.id, &dates::format_timestamp(&started_at), &dates::now_timestamp(), spent, outcome != Outcome::Aborted);
+    log(&format!("Recorded {} against todo {}", format_duration(spent), todo.id));
+
+    if outcome == Outcome::Finished && mark_todo_as_done(tdb, &[todo.id]).is_some_and(|missing| missing.is_empty()) {
+        log(&format!("Marked todo {} as done", todo.id));
+    }
+}
+
+pub fn handle_background_timer(tdb: &TodoDatabase, minutes: u64, todo_id: Option<i32>) {
+    let Ok(todo) = find_linked_todo(tdb, todo_id) else {
+        return;
+    };
+
+    let started_at = Local::now();
+    let ends_at = started_at + chrono::Duration::minutes(minutes as i64);
+    let timer_id = add_timer(tdb, todo.map(|todo| todo.id), &dates::format_timestamp(&started_at), &dates::format_timestamp(&ends_at));
+
+    match spawn_watcher(timer_id, &tdb.get_db_path()) {
+        Ok(()) => log(&format!("Started timer {} in the background, it ends at {}", timer_id, ends_at.format("%H:%M"))),
+        // The timer is still checked the next time tc runs
+        Err(e) => log(&format!("Failed to start the background timer {}: {}", timer_id, e)),
+    }
+}
+
+pub fn handle_timer_status(tdb: &TodoDatabase) {
+    let timers = list_running_timers(tdb);
+    if timers.is_empty() {
+        log("No timers running");
+        return;
+    }
+
+    let mut table = Table::new();
+    table.add_row(Row::new(vec![Cell::new("ID"), Cell::new("Todo"), Cell::new("Ends at"), Cell::new("Remaining")]));
+    for timer in timers {
+        let Some((_, ends_at)) = timer_times(tdb, &timer) else {
+            continue;
+        };
+        let todo = timer.todo_id.and_then(|id| get_todo(tdb, id)).map_or(String::new(), |todo| todo.title);
+        let remaining = format_duration((ends_at - Local::now()).num_seconds().max(0));
+        let ends_at = ends_at.format("%H:%M").to_string();
+        table.add_row(Row::new(vec![Cell::new(&timer.id.to_string()), Cell::new(&todo), Cell::new(&ends_at), Cell::new(&remaining)]));
+    }
+
+    table.printstd();
+}
+
+pub fn handle_timer_cancel(tdb: &TodoDatabase, timer_id: Option<i32>) {
+    let timers = match timer_id {
+        Some(id) => get_timer(tdb, id).into_iter().filter(|timer| timer.status == TIMER_RUNNING).collect(),
+        None => list_running_timers(tdb),
+    };
+    if timers.is_empty() {
+        log("No timers running");
+        return;
+    }
+
+    for timer in timers {
+        if !finish_timer(tdb, timer.id, TIMER_CANCELLED) {
+            continue;
+        }
+        log(&format!("Cancelled timer {}", timer.id));
+
+        // The time spent until the cancellation is recorded as an interrupted session
+        if let (Some(todo_id), Some(started_at)) = (timer.todo_id, dates::parse_timestamp(&timer.started_at)) {
+            let spent = (Local::now() - started_at).num_seconds();
+            add_session(tdb, todo_id, &timer.started_at, &dates::now_timestamp(), spent, false);
+            log(&format!("Recorded {} against todo {}", format_duration(spent), todo_id));
+        }
+    }
+}
+
+// Runs in the process started by `spawn_watcher`, waits until the timer elapses or is cancelled
+pub fn handle_timer_watch(tdb: &TodoDatabase, timer_id: i32, notify_settings: &NotifySettings) {
+    loop {
+        let Some(timer) = get_timer(tdb, timer_id) else {
+            return;
+        };
+        if timer.status != TIMER_RUNNING {
+            return;
+        }
+        let Some((started_at, ends_at)) = timer_times(tdb, &timer) else {
+            return;
+        };
+
+        let remaining = ends_at - Local::now();
+        if remaining <= chrono::Duration::zero() {
+            fire_timer(tdb, &timer, started_at, ends_at, notify_settings);
+            return;
+        }
+        // Wake up from time to time to notice cancelled timers
+        thread::sleep(remaining.to_std().unwrap_or_default().min(Duration::from_secs(10)));
+    }
+}
+
+// Fires timers which elapsed while no watcher was running, e.g. after a reboot
+pub fn fire_elapsed_timers(tdb: &TodoDatabase, notify_settings: &NotifySettings) {
+    for timer in list_running_timers(tdb) {
+        match timer_times(tdb, &timer) {
+            Some((started_at, ends_at)) if ends_at <= Local::now() => fire_timer(tdb, &timer, started_at, ends_at, notify_settings),
+            _ => {}
+        }
+    }
+}
+
+// Only the first process to mark the timer as fired shows the alert
+fn fire_timer(tdb: &TodoDatabase, timer: &BackgroundTimer, started_at: DateTime<Local>, ends_at: DateTime<Local>, notify_settings: &NotifySettings) {
+    if !finish_timer(tdb, timer.id, TIMER_FIRED) {
+        return;
+    }
+
+    if let Some(todo_id) = timer.todo_id {
+        let spent = (ends_at - started_at).num_seconds();
+        add_session(tdb, todo_id, &timer.started_at, &timer.ends_at, spent, true);
+    }
+    notify(notify_settings, "todo-cli", &format!("Time's up! Timer {} finished, did you finish your work?", timer.id));
+}
+
+// When a timer started and ends. Timers with a malformed timestamp are cancelled, as they would otherwise be
+// checked again on every invocation.
+fn timer_times(tdb: &TodoDatabase, timer: &BackgroundTimer) -> Option<(DateTime<Local>, DateTime<Local>)> {
+    match (dates::parse_timestamp(&timer.started_at), dates::parse_timestamp(&timer.ends_at)) {
+        (Some(started_at), Some(ends_at)) => Some((started_at, ends_at)),
+        _ => {
+            if finish_timer(tdb, timer.id, TIMER_CANCELLED) {
+                log(&format!(
+                    "Cancelled timer {}, it has an invalid start {} or end {}",
+                    timer.id, timer.started_at, timer.ends_at
+                ));
+            }
+            None
+        }
+    }
+}
+
+pub fn handle_show(tdb: &TodoDatabase, todo_id: i32, json: bool) {
+    let Some(todo) = get_todo(tdb, todo_id) else {
+        log(&format!("Todo {} not found", todo_id));
+        return;
+    };
+
+    let sessions = list_sessions(tdb, todo_id);
+    let time_spent: i64 = sessions.iter().map(|session| session.duration).sum();
+    let interrupted = sessions.iter().filter(|session| !session.completed).count();
+    let transitions = list_transitions(tdb, todo_id);
+    let now = Utc::now();
+
+    if json {
+        let history: Vec<_> = transitions
+            .iter()
+            .map(|transition| serde_json::json!({ "done": transition.done, "at": transition.at, "ago": dates::relative(&transition.at, now) }))
+            .collect();
+        let todo = serde_json::json!({
+            "id": todo.id,
+            "uuid": todo.uuid,
+            "title": todo.title,
+            "priority": todo.priority,
+            "done": todo.done,
+            "project": todo.project,
+            "parent_id": todo.parent_id,
+            "created_at": todo.created_at,
+            "created_ago": dates::relative(&todo.created_at, now),
+            "updated_at": todo.updated_at,
+            "updated_ago": dates::relative(&todo.updated_at, now),
+            "due_at": todo.due_at,
+            "scheduled_at": todo.scheduled_at,
+            "tags": todo.tags,
+            "context": todo.context,
+            "estimate": todo.estimate,
+            "notes": todo.notes,
+            "time_spent": time_spent,
+            "sessions": sessions.len(),
+            "interrupted_sessions": interrupted,
+            "history": history,
+        });
+        println!("{}", serde_json::to_string_pretty(&todo).expect("Failed to serialize todo"));
+        return;
+    }
+
+    // The local time with how long ago it was, e.g. `2026-10-16 09:12 (3 days ago)`
+    let when = |timestamp: &str| match dates::relative(timestamp, now) {
+        Some(relative) => format!("{} ({})", dates::format_local(timestamp), relative),
+        None => timestamp.to_string(),
+    };
+
+    let mut table = Table::new();
+    let rows = vec![
+        ("ID", todo.id.to_string()),
+        ("UUID", todo.uuid.clone().unwrap_or_default()),
+        ("Title", todo.title.clone()),
+        ("Priority", todo.priority.to_string()),
+        ("Done", if todo.done { "X" } else { "" }.to_string()),
+        ("Project", todo.project.clone().unwrap_or_default()),
+        ("Parent", todo.parent_id.map_or(String::new(), |id| id.to_string())),
+        ("Created", when(&todo.created_at)),
+        ("Updated", when(&todo.updated_at)),
+        ("Due", todo.due_at.as_deref().map_or(String::new(), when)),
+        ("Scheduled", todo.scheduled_at.as_deref().map_or(String::new(), when)),
+        ("Tags", todo.tags.iter().map(|tag| format!("#{}", tag)).collect::<Vec<_>>().join(" ")),
+        ("Context", todo.context.as_deref().map_or(String::new(), |context| format!("@{}", context))),
+        ("Estimate", todo.estimate.map_or(String::new(), format_duration)),
+        ("Time spent", format!("{} ({} sessions, {} interrupted)", format_duration(time_spent), sessions.len(), interrupted)),
+    ];
+    for (name, value) in rows {
+        table.add_row(Row::new(vec![Cell::new(name), Cell::new(&value)]));
+    }
+    table.printstd();
+
+    println!("\nHistory");
+    let mut history = Table::new();
+    history.add_row(Row::new(vec![Cell::new("Created"), Cell::new(&when(&todo.created_at))]));
+    for transition in &transitions {
+        let event = if transition.done { "Done" } else { "Undone" };
+        history.add_row(Row::new(vec![Cell::new(event), Cell::new(&when(&transition.at))]));
+    }
+    history.printstd();
+
+    if !todo.notes.is_empty() {
+        println!("\n{}", todo.notes);
+    }
+}
+
+pub fn handle_note(tdb: &TodoDatabase, todo_id: i32) {
+    let Some(todo) = get_todo(tdb, todo_id) else {
+        log(&format!("Todo {} not found", todo_id));
+        return;
+    };
+
+    let notes = match edit_text(&todo.notes, &format!("note-{}.md", todo_id)) {
+        Ok(notes) => notes,
+        Err(e) => {
+            log(&format!("Failed to edit notes: {}", e));
+            return;
+        }
+    };
+    // Editors add a final newline, it's not part of the notes
+    let notes = notes.trim_end();
+    if notes == todo.notes {
+        log(&format!("Notes of todo {} unchanged", todo_id));
+    } else if set_notes(tdb, todo_id, notes) {
+        log(&format!("Saved notes of todo {}: {}", todo_id, todo.title));
+    }
+}
+
+pub fn handle_edit(tdb: &TodoDatabase, keyword: &str, include_all: bool) {
+    let mut todos = if keyword.is_empty() { list_todos(tdb, include_all) } else { find_todos(tdb, keyword, include_all) };
+    if todos.is_empty() {
+        log("No todos to edit");
+        return;
+    }
+    todos.sort_by_key(|todo| todo.id);
+
+    let original = bulkedit::render(&todos);
+    let mut text = original.clone();
+    // A mistake reopens the editor with what was typed, so the edits aren't lost
+    let changes = loop {
+        text = match edit_text(&text, "edit.txt") {
+            Ok(text) => text,
+            Err(e) => {
+                log(&format!("Failed to edit todos: {}", e));
+                return;
+            }
+        };
+        if text == original {
+            log("No changes");
+            return;
+        }
+
+        match bulkedit::parse(&text).and_then(|lines| bulkedit::diff(&todos, lines)) {
+            Ok(changes) => break changes,
+            Err(e) => {
+                println!("{}", e.red());
+                let answer = user_input("Edit again? Otherwise nothing is changed (yes/no): ").unwrap_or_default();
+                if answer != "yes" && answer != "y" {
+                    log("Nothing was changed");
+                    return;
+                }
+            }
+        }
+    };
+    if changes.is_empty() {
+        log("No changes");
+        return;
+    }
+
+    for line in bulkedit::summarize(&changes) {
+        println!("{}", line);
+    }
+    let answer = user_input(&format!(
+        "Update {}, add {} and remove {} todos (yes/no)?: ",
+        changes.updated.len(),
+        changes.added.len(),
+        changes.removed.len()
+    ))
+    .unwrap_or_default();
+    if answer != "yes" && answer != "y" {
+        log("Nothing was changed");
+        return;
+    }
+
+    let updated: Vec<Todo> = changes.updated.into_iter().map(|(_, todo)| todo).collect();
+    match apply_edits(tdb, &updated, &changes.added, &changes.removed) {
+        Some(changed) if changed.is_empty() => {
+            log(&format!("Updated {}, added {} and removed {} todos", updated.len(), changes.added.len(), changes.removed.len()))
+        }
+        Some(changed) => log(&format!("Todo {:?} changed while editing, nothing was changed, run tc edit again", changed)),
+        None => log("Failed to apply the changes, nothing was changed"),
+    }
+}
+
+pub fn handle_stats(tdb: &TodoDatabase, since: &str, week: bool) {
+    let now = Local::now().naive_local();
+    let Some(since) = dates::parse(since, now) else {
+        log(&format!("Invalid date: {}, try e.g. {}", since, dates::DATE_EXAMPLES));
+        return;
+    };
+    let today = now.date();
+    if since.date > today {
+        log("The period starts in the future");
+        return;
+    }
+
+    let stats = stats::compute(&list_todos(tdb, true), since.date, today, week);
+    println!("Since {}, per {}\n", since.date, if week { "week" } else { "day" });
+
+    let created: Vec<usize> = stats.buckets.iter().map(|bucket| bucket.created).collect();
+    let completed: Vec<usize> = stats.buckets.iter().map(|bucket| bucket.completed).collect();
+    println!("Created    {} {}", stats::sparkline(&created).green(), stats.created());
+    println!("Completed  {} {}", stats::sparkline(&completed).blue(), stats.completed());
+    if let Some(rate) = stats.completion_rate {
+        println!("{:.0}% of the todos created since {} are done", rate * 100.0, since.date);
+    }
+
+    if week {
+        let max = created.iter().chain(&completed).copied().max().unwrap_or(0);
+        let mut table = Table::new();
+        table.add_row(Row::new(vec![Cell::new("Week"), Cell::new("Created"), Cell::new("Completed")]));
+        for bucket in &stats.buckets {
+            table.add_row(Row::new(vec![
+                Cell::new(&bucket.start.format("%Y-%m-%d").to_string()),
+                Cell::new(format!("{} {}", stats::bar(bucket.created, max, 20), bucket.created).trim_start()),
+                Cell::new(format!("{} {}", stats::bar(bucket.completed, max, 20), bucket.completed).trim_start()),
+            ]));
+        }
+        println!();
+        table.printstd();
+    }
+
+    let Some(average_age) = stats.average_open_age else {
+        println!("\nNo open todos");
+        return;
+    };
+    println!("\nOpen todos are {:.1} days old on average, the oldest:", average_age);
+    let mut table = Table::new();
+    table.add_row(Row::new(vec![Cell::new("ID"), Cell::new("Title"), Cell::new("Created")]));
+    for todo in &stats.oldest_open {
+        let age = dates::relative(&todo.created_at, Utc::now()).unwrap_or_default();
+        table.add_row(Row::new(vec![Cell::new(&todo.id.to_string()), Cell::new(&todo.title), Cell::new(&age)]));
+    }
+    table.printstd();
+
+    println!("\nOpen todos by priority");
+    let max = stats.priorities.values().copied().max().unwrap_or(0);
+    let mut table = Table::new();
+    for (priority, count) in stats.priorities.iter().rev() {
+        table.add_row(Row::new(vec![Cell::new(&priority.to_string()), Cell::new(&format!("{} {}", stats::bar(*count, max, 20), count))]));
+    }
+    table.printstd();
+}
+
+pub fn handle_standup(tdb: &TodoDatabase, since: &str, min_priority: i32) {
+    let now = Local::now().naive_local();
+    let Some(from) = dates::parse(since, now) else {
+        log(&format!("Invalid date: {}, try e.g. {}", since, dates::DATE_EXAMPLES));
+        return;
+    };
+
+    let todos = list_todos(tdb, true);
+    println!("## Standup {}\n", now.date());
+    print!("{}", standup::render(&todos, &dates::to_timestamp(from.start()), since.trim(), min_priority));
+}
+
+pub fn handle_review(tdb: &TodoDatabase, days: u64) {
+    let now = Local::now().naive_local();
+    // Both --days and review.days can be any number, which may reach past the dates chrono supports
+    let since = i64::try_from(days).ok().and_then(chrono::Duration::try_days).and_then(|duration| now.checked_sub_signed(duration));
+    let Some(since) = since else {
+        log(&format!("Invalid number of days: {}, it goes back too far", days));
+        return;
+    };
+    let todos = list_stale_todos(tdb, &dates::to_timestamp(since));
+    if todos.is_empty() {
+        log(&format!("Nothing to review, every open todo was updated or reviewed in the last {} days", days));
+        return;
+    }
+    log(&format!("{} open todos weren't updated in the last {} days", todos.len(), days));
+
+    let mut reviewed = 0;
+    for (i, todo) in todos.iter().enumerate() {
+        let updated = dates::relative(&todo.updated_at, Utc::now()).unwrap_or_default();
+        println!("\n[{}/{}] {} {} (priority {}, updated {})", i + 1, todos.len(), todo.id, todo.title.bold(), todo.priority, updated);
+        if let Some(due_at) = &todo.due_at {
+            println!("Due {}", dates::format_local(due_at));
+        }
+
+        // Asks again until the answer is one of the actions, the todo is kept on an empty answer
+        loop {
+            let Ok(answer) = user_input("k)eep, b)ump priority, r)eschedule, d)one, x) delete, e)dit or q)uit: ") else {
+                log(&format!("Reviewed {} todos", reviewed));
+                return;
+            };
+            match answer.to_lowercase().as_str() {
+                "" | "k" | "keep" => {
+                    mark_reviewed(tdb, todo.id);
+                }
+                "b" | "bump" => {
+                    if update_priority(tdb, todo.id, todo.priority + 1) {
+                        mark_reviewed(tdb, todo.id);
+                        log(&format!("Priority of todo {} is now {}", todo.id, todo.priority + 1));
+                    } else {
+                        log(&format!("Todo {} not found", todo.id));
+                    }
+                }
+                "r" | "reschedule" => {
+                    let date = user_input("Due date, e.g. fri, next week or 2026-11-01 (empty removes it): ").unwrap_or_default();
+                    let (updated, message) = if date.is_empty() {
+                        (set_due(tdb, todo.id, None), format!("Removed the due date of todo {}", todo.id))
+                    } else if let Some(date) = dates::parse(&date, now) {
+                        let due_at = dates::to_timestamp(date.start());
+                        (set_due(tdb, todo.id, Some(&due_at)), format!("Todo {} is due {}", todo.id, dates::format_local(&due_at)))
+                    } else {
+                        log(&format!("Invalid date: {}, try e.g. {}", date, dates::DATE_EXAMPLES));
+                        continue;
+                    };
+                    if updated {
+                        mark_reviewed(tdb, todo.id);
+                        log(&message);
+                    } else {
+                        log(&format!("Todo {} not found", todo.id));
+                    }
+                }
+                "d" | "done" => {
+                    if mark_todo_as_done(tdb, &[todo.id]).is_some_and(|missing| missing.is_empty()) {
+                        log(&format!("Marked todo {} as done", todo.id));
+                    }
+                }
+                "x" | "delete" => {
+                    if remove_todos_by_ids(tdb, &[todo.id]).is_some_and(|missing| missing.is_empty()) {
+                        log(&format!("Removed todo {}", todo.id));
+                    }
+                }
+                "e" | "edit" => {
+                    let title = user_input("New title (empty keeps it): ").unwrap_or_default();
+                    if !title.is_empty() {
+                        update_todo(tdb, todo.id, &title);
+                        log(&format!("Updated todo {}: {}", todo.id, title));
+                    }
+                    mark_reviewed(tdb, todo.id);
+                }
+                "q" | "quit" => {
+                    log(&format!("Reviewed {} todos", reviewed));
+                    return;
+                }
+                _ => {
+                    log(&format!("Unknown action: {}", answer));
+                    continue;
+                }
+            }
+            break;
+        }
+        reviewed += 1;
+    }
+    log(&format!("Reviewed all {} todos", reviewed));
+}
+
+pub fn handle_report_time(tdb: &TodoDatabase, week: bool) {
+    let today = Local::now().date_naive();
+    let (from, days) = if week {
+        (today - chrono::Duration::days(today.weekday().num_days_from_monday() as i64), 7)
+    } else {
+        (today, 1)
+    };
+    let until = from + chrono::Duration::days(days);
+
+    let sessions = list_sessions_between(tdb, &start_of_day(from), &start_of_day(until));
+    if sessions.is_empty() {
+        log(if week { "No time tracked this week" } else { "No time tracked today" });
+        return;
+    }
+
+    // Seconds spent per todo and day, keyed by todo id
+    let mut spent: BTreeMap<i32, (String, Vec<i64>)> = BTreeMap::new();
+    for session in &sessions {
+        let Some(started_at) = dates::local_date(&session.started_at) else {
+            continue;
+        };
+        let day = (started_at - from).num_days().clamp(0, days - 1) as usize;
+        let (_, per_day) = spent.entry(session.todo_id).or_insert_with(|| (session.title.clone(), vec![0; days as usize]));
+        per_day[day] += session.duration;
+    }
+
+    let mut table = Table::new();
+    let mut header = vec![Cell::new("ID"), Cell::new("Todo")];
+    header.extend((0..days).map(|day| Cell::new(&(from + chrono::Duration::days(day)).format("%a %m/%d").to_string())));
+    if days > 1 {
+        header.push(Cell::new("Total"));
+    }
+    table.add_row(Row::new(header));
+
+    let mut rows: Vec<(&i32, &(String, Vec<i64>))> = spent.iter().collect();
+    rows.sort_by_key(|(_, (_, per_day))| std::cmp::Reverse(per_day.iter().sum::<i64>()));
+
+    let mut totals = vec![0; days as usize];
+    for (id, (title, per_day)) in rows {
+        let mut row = vec![Cell::new(&id.to_string()), Cell::new(title)];
+        for (day, seconds) in per_day.iter().enumerate() {
+            totals[day] += seconds;
+            row.push(Cell::new(&if *seconds > 0 { format_duration(*seconds) } else { String::new() }));
+        }
+        if days > 1 {
+            row.push(Cell::new(&format_duration(per_day.iter().sum())));
+        }
+        table.add_row(Row::new(row));
+    }
+
+    let mut total_row = vec![Cell::new(""), Cell::new("Total")];
+    total_row.extend(totals.iter().map(|seconds| Cell::new(&format_duration(*seconds))));
+    if days > 1 {
+        total_row.push(Cell::new(&format_duration(totals.iter().sum())));
+    }
+    table.add_row(Row::new(total_row));
+
+    table.printstd();
+}
+
+fn start_of_day(date: NaiveDate) -> String {
+    dates::to_timestamp(date.and_time(NaiveTime::MIN))
+}
 
-    let standard_font = FIGfont::standard().unwrap();
+pub fn handle_import(tdb: &TodoDatabase, file: Option<&str>, format: Format) {
+    let mut input = String::new();
+    let read = match file {
+        Some(path) => fs::read_to_string(path).map(|content| input = content),
+        None => io::stdin().read_to_string(&mut input).map(|_| ()),
+    };
+    if let Err(e) = read {
+        log(&format!("Failed to read todos: {}", e));
+        return;
+    }
 
-    while Instant::now() < end_time {
-        let remaining = end_time - Instant::now();
-        let minutes_left = remaining.as_secs() / 60;
-        let seconds_left = remaining.as_secs() % 60;
+    // Each todo is paired with the position of its parent todo in the imported list
+    let entries = match format {
+        Format::Taskwarrior => taskwarrior::parse(&input).map(|todos| todos.into_iter().map(|todo| (todo, None)).collect()),
+        Format::Org => org::parse(&input),
+    };
+    let entries: Vec<(Todo, Option<usize>)> = match entries {
+        Ok(entries) => entries,
+        Err(e) => {
+            log(&e);
+            return;
+        }
+    };
 
-        let text = format!("{:02}:{:02}", minutes_left, seconds_left);
-        let figure = standard_font.convert(&text).unwrap();
-        let figure_string = figure.to_string();
-        let lines: Vec<&str> = figure_string.lines().collect();
-        let current_lines = lines.len();
+    match import_todos(tdb, &entries) {
+        Ok(ids) => log(&format!("Imported {} todos", ids.len())),
+        Err(e) => log(&format!("Failed to import todos, nothing was imported: {}", e)),
+    }
+}
 
-        // Clear the screen and move the cursor to the top
-        print!("\x1B[2J\x1B[H");
+pub fn handle_export(tdb: &TodoDatabase, file: Option<&str>, format: Format) {
+    let mut todos = list_todos(tdb, true);
 
-        // Get terminal size
-        let (width, height) = terminal_size().unwrap();
-        let term_width = width as usize;
-        let term_height = height as usize;
+    // Persist a uuid for every exported todo so importing the export back updates instead of duplicating
+    for todo in todos.iter_mut().filter(|todo| todo.uuid.is_none()) {
+        let uuid = uuid::Uuid::new_v4().to_string();
+        set_uuid(tdb, todo.id, &uuid);
+        todo.uuid = Some(uuid);
+    }
 
-        // Calculate vertical and horizontal padding
-        let vertical_padding = (term_height.saturating_sub(current_lines)) / 2;
-        let horizontal_padding = (term_width.saturating_sub(lines[0].len())) / 2;
+    let output = match format {
+        Format::Taskwarrior => taskwarrior::serialize(&todos),
+        Format::Org => Ok(org::serialize(&todos)),
+    };
+    let output = match output {
+        Ok(output) => output,
+        Err(e) => {
+            log(&e);
+            return;
+        }
+    };
 
-        // Print vertical padding
-        for _ in 0..vertical_padding {
-            println!();
+    match file {
+        Some(path) => match fs::write(path, output) {
+            Ok(_) => log(&format!("Exported {} todos to {}", todos.len(), path)),
+            Err(e) => log(&format!("Failed to write {}: {}", path, e)),
+        },
+        None => println!("{}", output),
+    }
+}
+
+// Creates .tc.db in the current directory, or a .tc marker naming the project when `project` is given
+pub fn handle_init(project: Option<&str>) {
+    let name = match project {
+        Some(_) => profiles::PROJECT_MARKER,
+        None => profiles::PROJECT_DATABASE,
+    };
+    let path = match env::current_dir() {
+        Ok(dir) => dir.join(name),
+        Err(e) => {
+            log(&format!("Failed to get the current directory: {}", e));
+            return;
         }
+    };
+    if path.exists() {
+        log(&format!("{} already exists", path.display()));
+        return;
+    }
+
+    let created = match project {
+        Some(project) => fs::write(&path, format!("{}\n", project)).map_err(|e| e.to_string()),
+        None => TodoDatabase::with_path(&path.to_string_lossy()).initialize().map_err(|e| e.to_string()),
+    };
+    match (created, project) {
+        (Ok(()), Some(project)) => log(&format!("Created {}, tc below this directory only shows todos of project {}, use --global for all", path.display(), project)),
+        (Ok(()), None) => log(&format!("Created {}, tc below this directory uses it instead of the global todos, use --global for those", path.display())),
+        (Err(e), _) => log(&format!("Failed to create {}: {}", path.display(), e)),
+    }
+}
+
+// `current_db` is the database in use, its profile is marked as the current one
+pub fn handle_profile_ls(current_db: Option<&Path>) {
+    let mut table = Table::new();
+    table.add_row(Row::new(vec![Cell::new(""), Cell::new("Profile"), Cell::new("Database")]));
+    for name in profiles::list_profiles() {
+        let Ok(path) = profiles::profile_path(&name) else {
+            continue;
+        };
+        let mark = if current_db == Some(path.as_path()) { "*" } else { "" };
+        table.add_row(Row::new(vec![Cell::new(mark), Cell::new(&name), Cell::new(&path.display().to_string())]));
+    }
 
-        // Print each line with horizontal padding
-        for line in &lines {
-            println!("{:width$}{}", "", line, width = horizontal_padding);
+    table.printstd();
+}
+
+pub fn handle_profile_create(name: &str) {
+    let path = match profiles::profile_path(name) {
+        Ok(path) => path,
+        Err(e) => {
+            log(&e);
+            return;
         }
+    };
+    if path.exists() {
+        log(&format!("Profile {} already exists", name));
+        return;
+    }
+
+    let tdb = TodoDatabase::with_path(&path.to_string_lossy());
+    match tdb.initialize() {
+        Ok(()) => log(&format!("Created profile {}, use it with tc --profile {} or tc profile switch {}", name, name, name)),
+        Err(e) => log(&format!("Failed to create profile {}: {}", name, e)),
+    }
+}
 
-        // Print left vertical padding
-        let left_padding = term_height.saturating_sub(current_lines + vertical_padding);
-        for _ in 0..left_padding {
-            println!();
+pub fn handle_profile_switch(name: &str, overrides: &[String]) {
+    match profiles::profile_path(name) {
+        Ok(path) if name == profiles::DEFAULT_PROFILE || path.exists() => {}
+        Ok(_) => {
+            log(&format!("Profile {} does not exist, create it with tc profile create {}", name, name));
+            return;
         }
+        Err(e) => {
+            log(&e);
+            return;
+        }
+    }
+    let Some(config_path) = settings::user_config_path() else {
+        log("Cannot find the user config file, HOME is not set");
+        return;
+    };
 
-        io::stdout().flush().unwrap();
-        sleep(Duration::from_secs(1));
+    if let Err(e) = settings::set_value(&config_path, "database.profile", name) {
+        log(&e);
+        return;
     }
+    log(&format!("Switched to profile {}", name));
 
-    show_alert("todo-cli", "Time's up! Did you finish your work?");
+    // A database path set anywhere wins over the profile
+    if let Ok(config) = Config::load(overrides) {
+        if let (Some(_), Some(source)) = (config.get("database.path"), config.source("database.path")) {
+            log(&format!("database.path is set by {}, it is used instead of the profile", source));
+        }
+    }
 }
 
-pub(crate) fn show_alert(title: &str, message: &str) {
-    if cfg!(target_os = "macos") {
-        Command::new("osascript")
-            .arg("-e")
-            .arg(format!("display alert \"{}\" message \"{}\"", title, message))
-            .stdout(std::process::Stdio::null()) // redirect output to /dev/null
-            .status()
-            .expect("failed to execute process");
-    } else if cfg!(target_os = "linux") {
-        // TODO: test in linux
-        Command::new("zenity")
-            .arg("--info")
-            .arg(format!("--text={}", message))
-            .status()
-            .expect("failed to execute process");
-    } else if cfg!(target_os = "windows") {
-        // TODO: test in window 
-        Command::new("msg")
-            .arg("*")
-            .arg(message)
-            .status()
-            .expect("failed to execute process");
+pub fn handle_config_get(key: &str, overrides: &[String]) {
+    if !settings::is_key(key) {
+        log(&format!("Unknown setting {}, see tc config list", key));
+        return;
+    }
+
+    match Config::load(overrides) {
+        Ok(config) => match config.get(key) {
+            Some(value) => println!("{}", settings::format_value(value)),
+            None => log(&format!("{} is not set", key)),
+        },
+        Err(e) => log(&e),
+    }
+}
+
+pub fn handle_config_set(key: &str, value: &str, local: bool) {
+    let path = if local {
+        settings::project_config_path().unwrap_or_else(|| env::current_dir().unwrap_or_default().join(settings::PROJECT_CONFIG))
     } else {
-        eprintln!("Unsupported OS");
+        let Some(path) = settings::user_config_path() else {
+            log("Cannot find the user config file, HOME is not set");
+            return;
+        };
+        path
+    };
+
+    match settings::set_value(&path, key, value) {
+        Ok(()) => log(&format!("Set {} to {} in {}", key, value, path.display())),
+        Err(e) => log(&e),
+    }
+}
+
+pub fn handle_config_list(overrides: &[String]) {
+    let config = match Config::load(overrides) {
+        Ok(config) => config,
+        Err(e) => {
+            log(&e);
+            return;
+        }
+    };
+
+    let mut table = Table::new();
+    table.add_row(Row::new(vec![Cell::new("Setting"), Cell::new("Value"), Cell::new("Source")]));
+    for key in settings::keys() {
+        let value = config.get(key).map(settings::format_value).unwrap_or_default();
+        let source = config.source(key).unwrap_or("");
+        table.add_row(Row::new(vec![Cell::new(key), Cell::new(&value), Cell::new(source)]));
+    }
+
+    table.printstd();
+}
+
+pub fn handle_config_path() {
+    match settings::user_config_path() {
+        Some(path) if path.is_file() => log(&format!("User config: {}", path.display())),
+        Some(path) => log(&format!("User config: {} (not created yet)", path.display())),
+        None => log("User config: none, HOME is not set"),
+    }
+    match settings::project_config_path() {
+        Some(path) => log(&format!("Project config: {}", path.display())),
+        None => log(&format!("Project config: none, create {} to add one", settings::PROJECT_CONFIG)),
+    }
+}
+
+pub fn handle_completions(shell: Shell) {
+    if let Err(e) = completion::write_registration(shell, &mut io::stdout()) {
+        log(&format!("Failed to print completions: {}", e));
+    }
+}
+
+pub fn handle_man(dir: Option<&str>) {
+    let result = match dir {
+        Some(dir) => fs::create_dir_all(dir).and_then(|_| completion::write_man_pages(Path::new(dir))),
+        None => completion::write_man_page(&mut io::stdout()),
+    };
+    match (result, dir) {
+        (Ok(()), Some(dir)) => log(&format!("Man pages written to {}", dir)),
+        (Ok(()), None) => {}
+        (Err(e), _) => log(&format!("Failed to write man pages: {}", e)),
+    }
+}
+
+pub fn handle_ui(tdb: &TodoDatabase, policy: DuplicatePolicy) {
+    if let Err(e) = tui::run(tdb, policy) {
+        log(&format!("Failed to run full-screen mode: {}", e));
     }
 }
 
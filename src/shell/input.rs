@@ -0,0 +1,56 @@
+use std::io;
+use std::os::unix::io::AsRawFd;
+use std::time::Duration;
+
+use termion::event::{parse_event, Event, Key};
+
+// Waits up to `timeout` for keyboard input and returns the keys pressed. Unlike termion's async_stdin this
+// doesn't leave a thread behind reading stdin, so the terminal can be handed back to the line editor afterwards.
+// Expects the terminal to be in raw mode.
+pub fn read_keys(timeout: Duration) -> io::Result<Vec<Key>> {
+    let fd = io::stdin().as_raw_fd();
+    let mut poll_fd = libc::pollfd { fd, events: libc::POLLIN, revents: 0 };
+
+    let ready = unsafe { libc::poll(&mut poll_fd, 1, timeout.as_millis() as libc::c_int) };
+    if ready < 0 {
+        let error = io::Error::last_os_error();
+        // A signal such as SIGWINCH interrupted the wait, treat it as a timeout
+        return if error.kind() == io::ErrorKind::Interrupted { Ok(Vec::new()) } else { Err(error) };
+    }
+    if ready == 0 {
+        return Ok(Vec::new());
+    }
+
+    let mut buffer = [0u8; 64];
+    let read = unsafe { libc::read(fd, buffer.as_mut_ptr() as *mut libc::c_void, buffer.len()) };
+    if read < 0 {
+        return Err(io::Error::last_os_error());
+    }
+
+    Ok(parse_keys(&buffer[..read as usize]))
+}
+
+fn parse_keys(bytes: &[u8]) -> Vec<Key> {
+    let mut keys = Vec::new();
+    let mut iter = bytes.iter().map(|byte| Ok(*byte)).peekable();
+    while let Some(Ok(byte)) = iter.next() {
+        // termion reads an escape byte as the start of a sequence, on its own it is the Esc key
+        if byte == b'\x1b' && iter.peek().is_none() {
+            keys.push(Key::Esc);
+        } else if let Ok(Event::Key(key)) = parse_event(byte, &mut iter) {
+            keys.push(key);
+        }
+    }
+    keys
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_keys_and_escape_sequences() {
+        let keys = parse_keys(b"j\x1b[A \x7f\x04\x1b");
+        assert_eq!(keys, vec![Key::Char('j'), Key::Up, Key::Char(' '), Key::Backspace, Key::Ctrl('d'), Key::Esc]);
+    }
+}
@@ -1,2 +1,8 @@
+pub(crate) mod completion;
+mod editor;
 pub(crate) mod entry;
 mod handlers;
+mod input;
+mod notifier;
+mod timer;
+mod tui;
@@ -0,0 +1,265 @@
+use std::fs::OpenOptions;
+use std::io::{self, Write};
+use std::os::unix::fs::OpenOptionsExt;
+use std::process::{Command, Stdio};
+
+use chrono::Local;
+
+use crate::settings::NotifySettings;
+use crate::utils::log;
+
+pub trait Notifier {
+    fn name(&self) -> &str;
+    fn notify(&self, title: &str, message: &str) -> io::Result<()>;
+}
+
+// Desktop notification through notify-send, which hands it to the notification daemon over D-Bus
+pub struct NotifySend;
+
+impl Notifier for NotifySend {
+    fn name(&self) -> &str {
+        "notify-send"
+    }
+
+    fn notify(&self, title: &str, message: &str) -> io::Result<()> {
+        run(Command::new("notify-send").arg("--app-name=todo-cli").arg(title).arg(message))
+    }
+}
+
+// Dialog native to the platform, waits until it is dismissed
+pub struct Dialog;
+
+impl Notifier for Dialog {
+    fn name(&self) -> &str {
+        "dialog"
+    }
+
+    fn notify(&self, title: &str, message: &str) -> io::Result<()> {
+        let mut command;
+        if cfg!(target_os = "macos") {
+            command = Command::new("osascript");
+            command.arg("-e").arg(format!(
+                "display alert \"{}\" message \"{}\"",
+                title.replace('"', "\\\""),
+                message.replace('"', "\\\"")
+            ));
+        } else if cfg!(target_os = "windows") {
+            command = Command::new("msg");
+            command.arg("*").arg(message);
+        } else {
+            command = Command::new("zenity");
+            command.arg("--info").arg(format!("--title={}", title)).arg(format!("--text={}", message));
+        }
+        run(&mut command)
+    }
+}
+
+// Rings the bell of the controlling terminal and prints the alert there
+pub struct Bell;
+
+impl Notifier for Bell {
+    fn name(&self) -> &str {
+        "bell"
+    }
+
+    fn notify(&self, title: &str, message: &str) -> io::Result<()> {
+        // Detached processes such as the background timer have no terminal, opening it fails
+        let mut tty = OpenOptions::new()
+            .write(true)
+            .open("/dev/tty")
+            .map_err(|e| io::Error::new(io::ErrorKind::NotFound, e))?;
+        // The terminal may be in raw mode while a countdown runs
+        write!(tty, "\x07{}: {}\r\n", title, message)?;
+        tty.flush()
+    }
+}
+
+// Appends the alert to a file, or writes it to a FIFO another program reads from
+pub struct File {
+    path: String,
+}
+
+impl Notifier for File {
+    fn name(&self) -> &str {
+        "file"
+    }
+
+    fn notify(&self, title: &str, message: &str) -> io::Result<()> {
+        // Without O_NONBLOCK opening a FIFO nobody reads from would block the timer forever
+        let mut file = OpenOptions::new()
+            .append(true)
+            .create(true)
+            .custom_flags(libc::O_NONBLOCK)
+            .open(&self.path)?;
+        writeln!(file, "{} {}: {}", Local::now().to_rfc3339(), title, message)
+    }
+}
+
+// Runs a user defined command, `{title}` and `{message}` in its arguments are replaced with the alert.
+// The template is split like a shell would but isn't run by one, so the alert text is never interpreted.
+pub struct CommandTemplate {
+    template: String,
+}
+
+impl CommandTemplate {
+    fn command(&self, title: &str, message: &str) -> io::Result<Command> {
+        let words = shlex::split(&self.template)
+            .filter(|words| !words.is_empty())
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, format!("Invalid notify command: {}", self.template)))?;
+
+        let mut args = words.iter().map(|word| word.replace("{title}", title).replace("{message}", message));
+        let mut command = Command::new(args.next().unwrap());
+        command.args(args);
+        Ok(command)
+    }
+}
+
+impl Notifier for CommandTemplate {
+    fn name(&self) -> &str {
+        "command"
+    }
+
+    fn notify(&self, title: &str, message: &str) -> io::Result<()> {
+        run(&mut self.command(title, message)?)
+    }
+}
+
+fn run(command: &mut Command) -> io::Result<()> {
+    let status = command.stdin(Stdio::null()).stdout(Stdio::null()).stderr(Stdio::null()).status()?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err(io::Error::other(format!("exited with {}", status)))
+    }
+}
+
+// Tries each notifier in turn, falling back to the next one when a backend is unavailable
+pub struct Notifiers {
+    notifiers: Vec<Box<dyn Notifier>>,
+}
+
+impl Notifiers {
+    pub fn from_settings(settings: &NotifySettings) -> Self {
+        let names = if settings.backends.is_empty() { default_notifiers(settings) } else { settings.backends.clone() };
+
+        let mut notifiers: Vec<Box<dyn Notifier>> = Vec::new();
+        for name in names {
+            match name.to_lowercase().as_str() {
+                "notify-send" | "dbus" => notifiers.push(Box::new(NotifySend)),
+                "dialog" => notifiers.push(Box::new(Dialog)),
+                "bell" => notifiers.push(Box::new(Bell)),
+                "file" => match &settings.file {
+                    Some(path) => notifiers.push(Box::new(File { path: path.clone() })),
+                    None => log("The file notifier needs the notify.file setting"),
+                },
+                "command" => match &settings.command {
+                    Some(template) => notifiers.push(Box::new(CommandTemplate { template: template.clone() })),
+                    None => log("The command notifier needs the notify.command setting"),
+                },
+                _ => log(&format!("Unknown notifier: {}", name)),
+            }
+        }
+
+        Notifiers { notifiers }
+    }
+
+    // Returns the name of the notifier which delivered the alert
+    pub fn notify(&self, title: &str, message: &str) -> Option<&str> {
+        for notifier in &self.notifiers {
+            match notifier.notify(title, message) {
+                Ok(()) => return Some(notifier.name()),
+                // A missing program just means the backend isn't available on this machine
+                Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+                Err(e) => log(&format!("Failed to notify with {}: {}", notifier.name(), e)),
+            }
+        }
+
+        // Every backend failed, print the alert so it isn't lost
+        log(&format!("{}: {}", title, message));
+        None
+    }
+}
+
+// Configured command and file first, then the desktop and finally the terminal
+fn default_notifiers(settings: &NotifySettings) -> Vec<String> {
+    let mut names = Vec::new();
+    if settings.command.is_some() {
+        names.push("command");
+    }
+    if settings.file.is_some() {
+        names.push("file");
+    }
+    if cfg!(target_os = "linux") {
+        names.push("notify-send");
+    }
+    names.push("dialog");
+    names.push("bell");
+    names.into_iter().map(|name| name.to_string()).collect()
+}
+
+pub fn notify(settings: &NotifySettings, title: &str, message: &str) {
+    Notifiers::from_settings(settings).notify(title, message);
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+    use std::fs;
+
+    use super::*;
+
+    struct Unavailable;
+
+    impl Notifier for Unavailable {
+        fn name(&self) -> &str {
+            "unavailable"
+        }
+
+        fn notify(&self, _title: &str, _message: &str) -> io::Result<()> {
+            Err(io::Error::from(io::ErrorKind::NotFound))
+        }
+    }
+
+    fn settings(backends: &[&str], command: Option<&str>, file: Option<&str>) -> NotifySettings {
+        NotifySettings {
+            backends: backends.iter().map(|name| name.to_string()).collect(),
+            command: command.map(|command| command.to_string()),
+            file: file.map(|file| file.to_string()),
+        }
+    }
+
+    #[test]
+    fn fall_back_to_next_notifier() {
+        let path = env::temp_dir().join(format!("tc_notify_{}.log", std::process::id()));
+        let notifiers = Notifiers {
+            notifiers: vec![Box::new(Unavailable), Box::new(File { path: path.to_string_lossy().to_string() })],
+        };
+
+        assert_eq!(notifiers.notify("todo-cli", "Time's up!"), Some("file"));
+        assert!(fs::read_to_string(&path).unwrap().ends_with(" todo-cli: Time's up!\n"));
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn command_template_replaces_placeholders() {
+        let notifier = CommandTemplate { template: "say -v 'Good News' \"{title}: {message}\"".to_string() };
+        let command = notifier.command("todo-cli", "Time's up; rm -rf ~").unwrap();
+
+        assert_eq!(command.get_program(), "say");
+        let args: Vec<_> = command.get_args().collect();
+        assert_eq!(args, vec!["-v", "Good News", "todo-cli: Time's up; rm -rf ~"]);
+
+        assert!(CommandTemplate { template: "say 'unclosed".to_string() }.command("", "").is_err());
+    }
+
+    #[test]
+    fn notifiers_from_settings() {
+        let names = |settings: &NotifySettings| -> Vec<String> {
+            Notifiers::from_settings(settings).notifiers.iter().map(|notifier| notifier.name().to_string()).collect()
+        };
+
+        assert_eq!(names(&settings(&["command", "bell", "file"], Some("true"), None)), vec!["command", "bell"]);
+        assert_eq!(names(&settings(&[], None, Some("/tmp/tc.fifo")))[0], "file");
+        assert_eq!(names(&settings(&[], None, None)).last().unwrap(), "bell");
+    }
+}
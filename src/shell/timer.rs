@@ -0,0 +1,374 @@
+use std::env;
+use std::io;
+use std::io::Write;
+use std::os::unix::process::CommandExt;
+use std::process::{Command, Stdio};
+use std::time::{Duration, Instant};
+
+use figlet_rs::FIGfont;
+use termion::event::Key;
+use termion::raw::IntoRawMode;
+use termion::terminal_size;
+
+use crate::shell::input::read_keys;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PhaseKind {
+    Work,
+    ShortBreak,
+    LongBreak,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Phase {
+    pub kind: PhaseKind,
+    pub minutes: u64,
+    // 1-based index of the work session this phase belongs to
+    pub cycle: u64,
+}
+
+impl Phase {
+    pub fn label(&self, cycles: u64) -> String {
+        let name = match self.kind {
+            PhaseKind::Work => "Work",
+            PhaseKind::ShortBreak => "Short break",
+            PhaseKind::LongBreak => "Long break",
+        };
+        format!("{} - cycle {}/{}", name, self.cycle, cycles)
+    }
+}
+
+pub struct Pomodoro {
+    pub work: u64,
+    pub short_break: u64,
+    pub long_break: u64,
+    pub cycles: u64,
+}
+
+impl Pomodoro {
+    // Every work session is followed by a short break, except the last one which is followed by a long break
+    pub fn phases(&self) -> Vec<Phase> {
+        let mut phases = Vec::new();
+        for cycle in 1..=self.cycles {
+            phases.push(Phase { kind: PhaseKind::Work, minutes: self.work, cycle });
+            if cycle < self.cycles {
+                phases.push(Phase { kind: PhaseKind::ShortBreak, minutes: self.short_break, cycle });
+            } else {
+                phases.push(Phase { kind: PhaseKind::LongBreak, minutes: self.long_break, cycle });
+            }
+        }
+        phases.retain(|phase| phase.minutes > 0);
+        phases
+    }
+}
+
+const HELP: &str = "space pause  +/- minute  d done  q quit";
+const STOPWATCH_HELP: &str = "space pause  l lap  d done  q quit";
+
+// Laps shown under the stopwatch, older ones are only printed once it stops
+const VISIBLE_LAPS: usize = 3;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Outcome {
+    // The countdown ran out
+    Completed,
+    // `d` was pressed to finish the work early
+    Finished,
+    // `q` was confirmed or Ctrl-C was pressed
+    Aborted,
+}
+
+// What the keys change while a countdown or stopwatch runs, kept apart from the terminal
+#[derive(Clone, Debug, PartialEq)]
+pub struct Clock {
+    // Time left for a countdown, none for a stopwatch which counts up
+    pub remaining: Option<Duration>,
+    // Time the clock ran, excluding pauses
+    pub elapsed: Duration,
+    pub laps: Vec<Duration>,
+    pub paused: bool,
+    // `q` was pressed, the next key confirms or cancels stopping
+    pub confirming_stop: bool,
+}
+
+impl Clock {
+    pub fn countdown(duration: Duration) -> Clock {
+        Clock { remaining: Some(duration), ..Clock::stopwatch() }
+    }
+
+    pub fn stopwatch() -> Clock {
+        Clock { remaining: None, elapsed: Duration::ZERO, laps: Vec::new(), paused: false, confirming_stop: false }
+    }
+
+    pub fn tick(&mut self, delta: Duration) {
+        if !self.paused {
+            self.remaining = self.remaining.map(|remaining| remaining.saturating_sub(delta));
+            self.elapsed += delta;
+        }
+    }
+
+    // Applies a key press, returns how the clock ended when the key stops it
+    pub fn press(&mut self, key: Key) -> Option<Outcome> {
+        if self.confirming_stop {
+            self.confirming_stop = false;
+            return match key {
+                // Stopping a countdown gives up on it, while a stopwatch is only ever done when stopped
+                Key::Char('y') | Key::Char('Y') | Key::Char('q') if self.remaining.is_some() => Some(Outcome::Aborted),
+                Key::Char('y') | Key::Char('Y') | Key::Char('q') => Some(Outcome::Completed),
+                _ => None,
+            };
+        }
+
+        match (key, self.remaining.as_mut()) {
+            (Key::Char(' '), _) => self.paused = !self.paused,
+            (Key::Char('+') | Key::Char('='), Some(remaining)) => *remaining += Duration::from_secs(60),
+            (Key::Char('-'), Some(remaining)) => *remaining = remaining.saturating_sub(Duration::from_secs(60)),
+            (Key::Char('l'), None) => self.laps.push(self.elapsed),
+            (Key::Char('d'), _) => return Some(Outcome::Finished),
+            (Key::Char('q') | Key::Esc, _) => self.confirming_stop = true,
+            (Key::Ctrl('c'), _) => return Some(Outcome::Aborted),
+            _ => {}
+        }
+        None
+    }
+
+    // The time shown in large digits
+    fn text(&self) -> String {
+        match self.remaining {
+            // Round up so the clock shows 00:01 during the last second rather than 00:00
+            Some(remaining) => format_clock((remaining.as_millis() as u64).div_ceil(1000)),
+            None => format_clock(self.elapsed.as_secs()),
+        }
+    }
+
+    fn status(&self) -> &'static str {
+        match (self.confirming_stop, self.remaining) {
+            (true, Some(_)) => "Stop the timer? (y/n)",
+            (true, None) => "Stop the stopwatch? (y/n)",
+            _ if self.paused => "Paused, press space to resume",
+            (_, Some(_)) => HELP,
+            (_, None) => STOPWATCH_HELP,
+        }
+    }
+}
+
+// Counts down from `duration`, redrawing the clock with the caption under it every second, until it runs out or
+// the user stops it. Returns how the countdown ended along with the time it actually ran, excluding pauses.
+pub fn countdown(duration: Duration, caption: &[String]) -> io::Result<(Outcome, Duration)> {
+    let (outcome, clock) = run(Clock::countdown(duration), caption)?;
+    Ok((outcome, clock.elapsed))
+}
+
+// Counts up from zero until the user stops it. Returns how it was stopped, the time it ran excluding pauses,
+// and the lap marks.
+pub fn stopwatch(caption: &[String]) -> io::Result<(Outcome, Duration, Vec<Duration>)> {
+    let (outcome, clock) = run(Clock::stopwatch(), caption)?;
+    Ok((outcome, clock.elapsed, clock.laps))
+}
+
+fn run(mut clock: Clock, caption: &[String]) -> io::Result<(Outcome, Clock)> {
+    let standard_font = FIGfont::standard().unwrap();
+    // Raw mode delivers key presses immediately, including Ctrl-C
+    let mut stdout = io::stdout().into_raw_mode()?;
+
+    let mut last_tick = Instant::now();
+    let mut last_frame = String::new();
+
+    loop {
+        let now = Instant::now();
+        clock.tick(now - last_tick);
+        last_tick = now;
+
+        if clock.remaining.is_some_and(|remaining| remaining.is_zero()) {
+            return Ok((Outcome::Completed, clock));
+        }
+
+        let text = clock.text();
+        let mut lines = caption.to_vec();
+        let first_visible = clock.laps.len().saturating_sub(VISIBLE_LAPS);
+        lines.extend((first_visible..clock.laps.len()).map(|index| format_lap(&clock.laps, index)));
+        lines.push(clock.status().to_string());
+
+        let frame = format!("{}{}", text, lines.join("\n"));
+        if frame != last_frame {
+            draw_clock(&mut stdout, &standard_font, &text, &lines)?;
+            last_frame = frame;
+        }
+
+        for key in read_keys(Duration::from_millis(200))? {
+            if let Some(outcome) = clock.press(key) {
+                return Ok((outcome, clock));
+            }
+        }
+    }
+}
+
+// `05:30`, or `1:05:30` once past an hour
+pub fn format_clock(seconds: u64) -> String {
+    if seconds >= 3600 {
+        format!("{}:{:02}:{:02}", seconds / 3600, seconds % 3600 / 60, seconds % 60)
+    } else {
+        format!("{:02}:{:02}", seconds / 60, seconds % 60)
+    }
+}
+
+// `Lap 2  05:30  (+02:10)`, the time of the lap mark followed by the time since the previous one
+pub fn format_lap(laps: &[Duration], index: usize) -> String {
+    let previous = if index == 0 { Duration::ZERO } else { laps[index - 1] };
+    format!(
+        "Lap {}  {}  (+{})",
+        index + 1,
+        format_clock(laps[index].as_secs()),
+        format_clock((laps[index] - previous).as_secs())
+    )
+}
+
+// Starts `tc --db <path> t watch <id>` in a session of its own, detached from the terminal, so the background timer keeps
+// running after the terminal is closed
+pub fn spawn_watcher(timer_id: i32, db_path: &str) -> io::Result<()> {
+    let mut command = Command::new(env::current_exe()?);
+    command
+        .args(["--db", db_path, "t", "watch", &timer_id.to_string()])
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null());
+    unsafe {
+        command.pre_exec(|| {
+            if libc::setsid() < 0 {
+                return Err(io::Error::last_os_error());
+            }
+            Ok(())
+        });
+    }
+
+    command.spawn().map(|_| ())
+}
+
+// Draws the text in FIGfont in the center of the screen, followed by the caption lines.
+// Lines end with \r\n as the terminal is in raw mode.
+pub fn draw_clock(out: &mut impl Write, font: &FIGfont, text: &str, caption: &[String]) -> io::Result<()> {
+    let figure = font.convert(text).unwrap();
+    let figure_string = figure.to_string();
+    let mut lines: Vec<String> = figure_string.lines().map(|line| line.to_string()).collect();
+    let figure_width = lines[0].len();
+    if !caption.is_empty() {
+        lines.push(String::new());
+    }
+    let current_lines = lines.len() + caption.len();
+
+    // Clear the screen and move the cursor to the top
+    let mut screen = String::from("\x1B[2J\x1B[H");
+
+    // Get terminal size
+    let (width, height) = terminal_size()?;
+    let term_width = width as usize;
+    let term_height = height as usize;
+
+    // Calculate vertical and horizontal padding
+    let vertical_padding = (term_height.saturating_sub(current_lines)) / 2;
+    let horizontal_padding = (term_width.saturating_sub(figure_width)) / 2;
+
+    // Print vertical padding
+    screen.push_str(&"\r\n".repeat(vertical_padding));
+
+    // Print each line with horizontal padding
+    for line in &lines {
+        screen.push_str(&format!("{:width$}{}\r\n", "", line, width = horizontal_padding));
+    }
+
+    // Captions are centered on their own
+    for line in caption {
+        let caption_padding = term_width.saturating_sub(line.chars().count()) / 2;
+        screen.push_str(&format!("{:width$}{}\r\n", "", line, width = caption_padding));
+    }
+
+    out.write_all(screen.as_bytes())?;
+    out.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pomodoro_phases() {
+        let pomodoro = Pomodoro { work: 25, short_break: 5, long_break: 15, cycles: 3 };
+        let phases = pomodoro.phases();
+
+        let kinds: Vec<PhaseKind> = phases.iter().map(|phase| phase.kind).collect();
+        assert_eq!(kinds, vec![
+            PhaseKind::Work, PhaseKind::ShortBreak,
+            PhaseKind::Work, PhaseKind::ShortBreak,
+            PhaseKind::Work, PhaseKind::LongBreak,
+        ]);
+        assert_eq!(phases[5].minutes, 15);
+        assert_eq!(phases[5].label(3), "Long break - cycle 3/3");
+    }
+
+    #[test]
+    fn format_clock_and_laps() {
+        assert_eq!(format_clock(65), "01:05");
+        assert_eq!(format_clock(3930), "1:05:30");
+
+        let laps = vec![Duration::from_secs(90), Duration::from_secs(330)];
+        assert_eq!(format_lap(&laps, 0), "Lap 1  01:30  (+01:30)");
+        assert_eq!(format_lap(&laps, 1), "Lap 2  05:30  (+04:00)");
+    }
+
+    #[test]
+    fn countdown_keys() {
+        let mut clock = Clock::countdown(Duration::from_secs(300));
+        clock.tick(Duration::from_secs(10));
+        assert_eq!((clock.remaining, clock.elapsed), (Some(Duration::from_secs(290)), Duration::from_secs(10)));
+
+        assert_eq!(clock.press(Key::Char(' ')), None);
+        clock.tick(Duration::from_secs(10));
+        assert!(clock.paused);
+        assert_eq!(clock.elapsed, Duration::from_secs(10));
+        clock.press(Key::Char(' '));
+
+        clock.press(Key::Char('+'));
+        assert_eq!(clock.remaining, Some(Duration::from_secs(350)));
+        clock.press(Key::Char('-'));
+        clock.press(Key::Char('-'));
+        assert_eq!(clock.remaining, Some(Duration::from_secs(230)));
+        // Laps are only taken by the stopwatch
+        clock.press(Key::Char('l'));
+        assert!(clock.laps.is_empty());
+
+        // Any other key than y cancels stopping
+        assert_eq!(clock.press(Key::Char('q')), None);
+        assert_eq!(clock.status(), "Stop the timer? (y/n)");
+        assert_eq!(clock.press(Key::Char('n')), None);
+        assert!(!clock.confirming_stop);
+        clock.press(Key::Esc);
+        assert_eq!(clock.press(Key::Char('y')), Some(Outcome::Aborted));
+
+        assert_eq!(clock.press(Key::Char('d')), Some(Outcome::Finished));
+        assert_eq!(clock.press(Key::Ctrl('c')), Some(Outcome::Aborted));
+    }
+
+    #[test]
+    fn stopwatch_keys() {
+        let mut clock = Clock::stopwatch();
+        clock.tick(Duration::from_secs(30));
+        clock.press(Key::Char('l'));
+        clock.press(Key::Char('+'));
+        clock.tick(Duration::from_secs(45));
+        clock.press(Key::Char('l'));
+        assert_eq!(clock.remaining, None);
+        assert_eq!(clock.laps, vec![Duration::from_secs(30), Duration::from_secs(75)]);
+        assert_eq!(clock.text(), "01:15");
+
+        clock.press(Key::Char('q'));
+        assert_eq!(clock.status(), "Stop the stopwatch? (y/n)");
+        assert_eq!(clock.press(Key::Char('q')), Some(Outcome::Completed));
+        assert_eq!(clock.press(Key::Ctrl('c')), Some(Outcome::Aborted));
+    }
+
+    #[test]
+    fn pomodoro_phases_skip_empty_breaks() {
+        let pomodoro = Pomodoro { work: 25, short_break: 0, long_break: 0, cycles: 2 };
+        assert_eq!(pomodoro.phases().len(), 2);
+    }
+}
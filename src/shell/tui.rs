@@ -0,0 +1,364 @@
+use std::cmp::Reverse;
+use std::io::{self, Write};
+use std::time::Duration;
+
+use chrono::Local;
+use termion::event::Key;
+use termion::raw::IntoRawMode;
+use termion::screen::IntoAlternateScreen;
+use termion::{clear, cursor, style, terminal_size};
+use unicode_width::UnicodeWidthChar;
+
+use crate::database::{Added, Todo, TodoDatabase};
+use crate::operations::{add_new_todo, find_todos, list_todos, mark_todo_as_done, mark_todo_as_undone, remove_todos_by_ids, update_priority, update_todo};
+use crate::quickadd;
+use crate::settings::DuplicatePolicy;
+use crate::shell::input::read_keys;
+
+const HELP: &str = "j/k move  space done  e edit  +/- priority  dd delete  a add  / filter  s show done  q quit";
+
+// Rows taken by the title bar, the column header and the status line
+const CHROME_ROWS: usize = 3;
+
+#[derive(Debug, PartialEq)]
+enum Mode {
+    Normal,
+    Filter,
+    Edit,
+    Add,
+    // `d` was pressed once, a second `d` deletes the selected todo
+    Delete,
+}
+
+struct App {
+    todos: Vec<Todo>,
+    selected: usize,
+    // Index of the first todo shown on screen
+    offset: usize,
+    filter: String,
+    include_all: bool,
+    mode: Mode,
+    input: String,
+    message: String,
+    // add.on_duplicate setting
+    policy: DuplicatePolicy,
+}
+
+impl App {
+    fn new(policy: DuplicatePolicy) -> Self {
+        App {
+            todos: Vec::new(),
+            selected: 0,
+            offset: 0,
+            filter: String::new(),
+            include_all: false,
+            mode: Mode::Normal,
+            input: String::new(),
+            message: String::new(),
+            policy,
+        }
+    }
+
+    fn reload(&mut self, tdb: &TodoDatabase) {
+        self.todos = if self.filter.is_empty() {
+            list_todos(tdb, self.include_all)
+        } else {
+            find_todos(tdb, &self.filter, self.include_all)
+        };
+        self.todos.sort_by_key(|todo| Reverse(todo.id));
+        self.move_selection(0);
+    }
+
+    fn selected_todo(&self) -> Option<&Todo> {
+        self.todos.get(self.selected)
+    }
+
+    fn move_selection(&mut self, delta: isize) {
+        let last = self.todos.len().saturating_sub(1);
+        self.selected = self.selected.saturating_add_signed(delta).min(last);
+    }
+
+    // Keeps the selected todo within the visible rows
+    fn scroll(&mut self, visible_rows: usize) {
+        if self.selected < self.offset {
+            self.offset = self.selected;
+        } else if visible_rows > 0 && self.selected >= self.offset + visible_rows {
+            self.offset = self.selected + 1 - visible_rows;
+        }
+        self.offset = self.offset.min(self.todos.len().saturating_sub(visible_rows));
+    }
+
+    // Returns false once the user asked to quit
+    fn handle_key(&mut self, tdb: &TodoDatabase, key: Key, page: usize) -> bool {
+        match self.mode {
+            Mode::Normal => return self.handle_normal_key(tdb, key, page),
+            Mode::Delete => {
+                self.mode = Mode::Normal;
+                if key == Key::Char('d') {
+                    if let Some(id) = self.selected_todo().map(|todo| todo.id) {
+                        remove_todos_by_ids(tdb, &[id]);
+                        self.message = format!("Removed todo {}", id);
+                        self.reload(tdb);
+                    }
+                }
+            }
+            Mode::Filter => match key {
+                Key::Char('\n') => self.mode = Mode::Normal,
+                Key::Esc => {
+                    self.filter.clear();
+                    self.mode = Mode::Normal;
+                    self.reload(tdb);
+                }
+                Key::Backspace => {
+                    self.filter.pop();
+                    self.reload(tdb);
+                }
+                Key::Char(c) if !c.is_control() => {
+                    self.filter.push(c);
+                    self.selected = 0;
+                    self.reload(tdb);
+                }
+                _ => {}
+            },
+            Mode::Edit | Mode::Add => match key {
+                Key::Char('\n') => self.submit_input(tdb),
+                Key::Esc => {
+                    self.input.clear();
+                    self.mode = Mode::Normal;
+                }
+                Key::Backspace => {
+                    self.input.pop();
+                }
+                Key::Char(c) if !c.is_control() => self.input.push(c),
+                _ => {}
+            },
+        }
+
+        true
+    }
+
+    fn handle_normal_key(&mut self, tdb: &TodoDatabase, key: Key, page: usize) -> bool {
+        self.message.clear();
+        let half_page = (page / 2).max(1) as isize;
+
+        match key {
+            Key::Char('q') | Key::Ctrl('c') => return false,
+            Key::Char('j') | Key::Down => self.move_selection(1),
+            Key::Char('k') | Key::Up => self.move_selection(-1),
+            Key::Char('g') | Key::Home => self.selected = 0,
+            Key::Char('G') | Key::End => self.move_selection(isize::MAX),
+            Key::Ctrl('d') | Key::PageDown => self.move_selection(half_page),
+            Key::Ctrl('u') | Key::PageUp => self.move_selection(-half_page),
+            Key::Char(' ') | Key::Char('x') => {
+                if let Some(todo) = self.selected_todo() {
+                    let (id, done) = (todo.id, todo.done);
+                    if done {
+                        mark_todo_as_undone(tdb, &[id]);
+                        self.message = format!("Marked todo {} as undone", id);
+                    } else {
+                        mark_todo_as_done(tdb, &[id]);
+                        self.message = format!("Marked todo {} as done", id);
+                    }
+                    self.reload(tdb);
+                }
+            }
+            Key::Char('+') | Key::Char('-') => {
+                if let Some(todo) = self.selected_todo() {
+                    let priority = if key == Key::Char('+') { todo.priority + 1 } else { (todo.priority - 1).max(0) };
+                    update_priority(tdb, todo.id, priority);
+                    self.reload(tdb);
+                }
+            }
+            Key::Char('e') => {
+                if let Some(title) = self.selected_todo().map(|todo| todo.title.clone()) {
+                    self.input = title;
+                    self.mode = Mode::Edit;
+                }
+            }
+            Key::Char('d') if self.selected_todo().is_some() => self.mode = Mode::Delete,
+            Key::Char('a') => {
+                self.input.clear();
+                self.mode = Mode::Add;
+            }
+            Key::Char('/') => self.mode = Mode::Filter,
+            Key::Char('s') => {
+                self.include_all = !self.include_all;
+                self.reload(tdb);
+            }
+            Key::Esc if !self.filter.is_empty() => {
+                self.filter.clear();
+                self.reload(tdb);
+            }
+            _ => {}
+        }
+
+        true
+    }
+
+    fn submit_input(&mut self, tdb: &TodoDatabase) {
+        let text = self.input.trim().to_string();
+        if text.is_empty() {
+            self.message = "Todo cannot be empty".to_string();
+        } else if self.mode == Mode::Add {
+            // Same inline tokens as `tc a`, e.g. `Buy milk !2 #home`
+            let todo = quickadd::parse(&text, Local::now().naive_local());
+            if todo.title.is_empty() {
+                self.message = "Todo cannot be empty".to_string();
+            } else {
+                self.message = match add_new_todo(tdb, &todo, self.policy) {
+                    Added::New { .. } => format!("Added task: {}", todo.title),
+                    Added::Rejected { id } => format!("Todo {} has the same title, not adding it", id),
+                    Added::Merged { id, priority } => format!("Merged into todo {}, its priority is now {}", id, priority),
+                };
+            }
+        } else if let Some(id) = self.selected_todo().map(|todo| todo.id) {
+            update_todo(tdb, id, &text);
+            self.message = format!("Updated task: {}", text);
+        }
+
+        self.input.clear();
+        self.mode = Mode::Normal;
+        self.reload(tdb);
+    }
+
+    fn render(&mut self, out: &mut impl Write, (width, height): (u16, u16)) -> io::Result<()> {
+        let width = width as usize;
+        let visible_rows = (height as usize).saturating_sub(CHROME_ROWS);
+        self.scroll(visible_rows);
+
+        let mut screen = String::new();
+
+        let mut title = format!(" tc  {} todos", self.todos.len());
+        if self.include_all {
+            title.push_str("  (including done)");
+        }
+        if !self.filter.is_empty() {
+            title.push_str(&format!("  filter: {}", self.filter));
+        }
+        screen.push_str(&format!("{}{}{}{}{}", cursor::Goto(1, 1), clear::CurrentLine, style::Invert, pad(&title, width), style::Reset));
+        screen.push_str(&format!("{}{}{}{}{}", cursor::Goto(1, 2), clear::CurrentLine, style::Bold, pad("    ID  Pri  Done  Title", width), style::Reset));
+
+        for row in 0..visible_rows {
+            screen.push_str(&format!("{}{}", cursor::Goto(1, (row + 3) as u16), clear::CurrentLine));
+            let index = self.offset + row;
+            let Some(todo) = self.todos.get(index) else {
+                continue;
+            };
+
+            let line = format!("{:>6}  {:>3}  {:^4}  {}", todo.id, todo.priority, if todo.done { "X" } else { "" }, todo.title);
+            if index == self.selected {
+                screen.push_str(&format!("{}{}{}", style::Invert, pad(&line, width), style::Reset));
+            } else {
+                screen.push_str(&pad(&line, width));
+            }
+        }
+
+        let status = match self.mode {
+            Mode::Normal if self.message.is_empty() => HELP.to_string(),
+            Mode::Normal => self.message.clone(),
+            Mode::Delete => "Press d again to delete the selected todo".to_string(),
+            Mode::Filter => format!("/{}", self.filter),
+            Mode::Edit => format!("Edit: {}", self.input),
+            Mode::Add => format!("Add: {}", self.input),
+        };
+        screen.push_str(&format!("{}{}{}", cursor::Goto(1, height), clear::CurrentLine, pad(&status, width).trim_end()));
+
+        let typing = matches!(self.mode, Mode::Filter | Mode::Edit | Mode::Add);
+        screen.push_str(&if typing { cursor::Show.to_string() } else { cursor::Hide.to_string() });
+
+        out.write_all(screen.as_bytes())?;
+        out.flush()
+    }
+}
+
+// Truncates or pads the text with spaces to exactly `width` terminal columns
+fn pad(text: &str, width: usize) -> String {
+    let mut padded = String::new();
+    let mut used = 0;
+    for c in text.chars() {
+        let char_width = c.width().unwrap_or(0);
+        if used + char_width > width {
+            break;
+        }
+        padded.push(c);
+        used += char_width;
+    }
+    padded.push_str(&" ".repeat(width - used));
+    padded
+}
+
+pub fn run(tdb: &TodoDatabase, policy: DuplicatePolicy) -> io::Result<()> {
+    let mut screen = io::stdout().into_raw_mode()?.into_alternate_screen()?;
+
+    let mut app = App::new(policy);
+    app.reload(tdb);
+
+    let mut size = terminal_size()?;
+    let mut dirty = true;
+    loop {
+        // termion has no resize event, compare the size on every tick instead
+        let current_size = terminal_size()?;
+        if dirty || current_size != size {
+            size = current_size;
+            write!(screen, "{}", clear::All)?;
+            app.render(&mut screen, size)?;
+            dirty = false;
+        }
+
+        let page = (size.1 as usize).saturating_sub(CHROME_ROWS);
+        for key in read_keys(Duration::from_millis(200))? {
+            dirty = true;
+            if !app.handle_key(tdb, key, page) {
+                write!(screen, "{}", cursor::Show)?;
+                return screen.flush();
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn app_with_todos(count: i32) -> App {
+        let mut app = App::new(DuplicatePolicy::CompletePrevious);
+        app.todos = (1..=count).map(|id| Todo::new(id, &format!("Todo {}", id))).collect();
+        app
+    }
+
+    #[test]
+    fn move_selection_stays_within_todos() {
+        let mut app = app_with_todos(3);
+        app.move_selection(-1);
+        assert_eq!(app.selected, 0);
+        app.move_selection(isize::MAX);
+        assert_eq!(app.selected, 2);
+
+        let mut empty = app_with_todos(0);
+        empty.move_selection(1);
+        assert_eq!(empty.selected, 0);
+    }
+
+    #[test]
+    fn scroll_keeps_selection_visible() {
+        let mut app = app_with_todos(20);
+        app.selected = 12;
+        app.scroll(5);
+        assert_eq!(app.offset, 8);
+
+        app.selected = 3;
+        app.scroll(5);
+        assert_eq!(app.offset, 3);
+
+        // Growing the terminal shows as many todos as fit
+        app.scroll(30);
+        assert_eq!(app.offset, 0);
+    }
+
+    #[test]
+    fn pad_to_terminal_width() {
+        assert_eq!(pad("abc", 5), "abc  ");
+        assert_eq!(pad("abcdef", 4), "abcd");
+        assert_eq!(pad("할일", 3), "할 ");
+    }
+}
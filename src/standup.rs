@@ -0,0 +1,84 @@
+use crate::database::Todo;
+use crate::dates;
+
+// A Markdown summary to paste into chat: what was done and added since `since`, a timestamp, and the open todos
+// with at least `min_priority`. `since_label` is how `since` reads in the headings, e.g. `yesterday`.
+pub fn render(todos: &[Todo], since: &str, since_label: &str, min_priority: i32) -> String {
+    // Done todos were last updated when they were marked as done
+    let mut completed: Vec<&Todo> = todos.iter().filter(|todo| todo.done && todo.updated_at.as_str() >= since).collect();
+    completed.sort_by(|a, b| a.updated_at.cmp(&b.updated_at));
+    let mut created: Vec<&Todo> = todos.iter().filter(|todo| todo.created_at.as_str() >= since).collect();
+    created.sort_by(|a, b| a.created_at.cmp(&b.created_at));
+    let mut open: Vec<&Todo> = todos.iter().filter(|todo| !todo.done && todo.priority >= min_priority).collect();
+    open.sort_by_key(|todo| (std::cmp::Reverse(todo.priority), todo.id));
+
+    let mut text = String::new();
+    section(&mut text, &format!("Done since {}", since_label), completed.iter().map(|todo| format!("- [x] {}", todo.title)));
+    section(
+        &mut text,
+        &format!("Added since {}", since_label),
+        created.iter().map(|todo| format!("- [{}] {}", if todo.done { "x" } else { " " }, todo.title)),
+    );
+    section(
+        &mut text,
+        &format!("Open with priority {} or higher", min_priority),
+        open.iter().map(|todo| match &todo.due_at {
+            Some(due_at) => format!("- [ ] {} (priority {}, due {})", todo.title, todo.priority, dates::format_local(due_at)),
+            None => format!("- [ ] {} (priority {})", todo.title, todo.priority),
+        }),
+    );
+    text
+}
+
+fn section(text: &mut String, heading: &str, items: impl Iterator<Item = String>) {
+    if !text.is_empty() {
+        text.push('\n');
+    }
+    text.push_str(&format!("### {}\n", heading));
+    let mut empty = true;
+    for item in items {
+        text.push_str(&item);
+        text.push('\n');
+        empty = false;
+    }
+    if empty {
+        text.push_str("- Nothing\n");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn todo(title: &str, priority: i32, done: bool, created_at: &str, updated_at: &str) -> Todo {
+        Todo {
+            done,
+            priority,
+            created_at: created_at.to_string(),
+            updated_at: updated_at.to_string(),
+            ..Todo::new(0, title)
+        }
+    }
+
+    #[test]
+    fn render_standup() {
+        let todos = vec![
+            todo("Write report", 3, true, "2026-10-10T09:00:00Z", "2026-10-18T16:00:00Z"),
+            todo("Buy milk", 0, false, "2026-10-18T08:00:00Z", "2026-10-18T08:00:00Z"),
+            todo("Old done", 0, true, "2026-10-01T09:00:00Z", "2026-10-02T09:00:00Z"),
+            todo("Fix login", 2, false, "2026-10-05T09:00:00Z", "2026-10-05T09:00:00Z"),
+            todo("Release", 5, false, "2026-10-06T09:00:00Z", "2026-10-06T09:00:00Z"),
+        ];
+
+        let text = render(&todos, "2026-10-18T00:00:00Z", "yesterday", 2);
+        assert_eq!(
+            text,
+            "### Done since yesterday\n- [x] Write report\n\n\
+             ### Added since yesterday\n- [ ] Buy milk\n\n\
+             ### Open with priority 2 or higher\n- [ ] Release (priority 5)\n- [ ] Fix login (priority 2)\n"
+        );
+
+        let text = render(&todos, "2026-10-19T00:00:00Z", "today", 9);
+        assert_eq!(text.matches("- Nothing").count(), 3);
+    }
+}
@@ -0,0 +1,180 @@
+use std::collections::BTreeMap;
+
+use chrono::{Datelike, Duration, NaiveDate};
+
+use crate::database::Todo;
+use crate::dates;
+
+const SPARKS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+// Todos created and completed in a day, or a week starting on monday
+#[derive(Debug, PartialEq)]
+pub struct Bucket {
+    pub(crate) start: NaiveDate,
+    pub(crate) created: usize,
+    pub(crate) completed: usize,
+}
+
+#[derive(Debug)]
+pub struct Stats {
+    pub(crate) buckets: Vec<Bucket>,
+    // Share of the todos created in the period that are done
+    pub(crate) completion_rate: Option<f64>,
+    // Days since the open todos were created, on average
+    pub(crate) average_open_age: Option<f64>,
+    pub(crate) oldest_open: Vec<Todo>,
+    // Open todos per priority
+    pub(crate) priorities: BTreeMap<i32, usize>,
+}
+
+impl Stats {
+    pub fn created(&self) -> usize {
+        self.buckets.iter().map(|bucket| bucket.created).sum()
+    }
+
+    pub fn completed(&self) -> usize {
+        self.buckets.iter().map(|bucket| bucket.completed).sum()
+    }
+}
+
+// Counts the todos created from `since` to `today`, and the done todos last updated then, which is when they were completed
+pub fn compute(todos: &[Todo], since: NaiveDate, today: NaiveDate, week: bool) -> Stats {
+    let bucket_start = |date: NaiveDate| {
+        if week {
+            date - Duration::days(date.weekday().num_days_from_monday() as i64)
+        } else {
+            date
+        }
+    };
+    let step = Duration::days(if week { 7 } else { 1 });
+
+    let mut buckets = Vec::new();
+    let mut start = bucket_start(since);
+    while start <= today {
+        buckets.push(Bucket { start, created: 0, completed: 0 });
+        start += step;
+    }
+    let index = |date: NaiveDate| {
+        (since..=today)
+            .contains(&date)
+            .then(|| buckets.iter().position(|bucket| bucket.start == bucket_start(date)))
+            .flatten()
+    };
+
+    let mut created = Vec::new();
+    let mut completed = Vec::new();
+    for todo in todos {
+        if let Some(i) = dates::local_date(&todo.created_at).and_then(index) {
+            created.push(i);
+        }
+        if let Some(i) = dates::local_date(&todo.updated_at).filter(|_| todo.done).and_then(index) {
+            completed.push(i);
+        }
+    }
+    for i in &created {
+        buckets[*i].created += 1;
+    }
+    for i in completed {
+        buckets[i].completed += 1;
+    }
+
+    let created_in_period: Vec<&Todo> = todos
+        .iter()
+        .filter(|todo| dates::local_date(&todo.created_at).is_some_and(|date| (since..=today).contains(&date)))
+        .collect();
+    let completion_rate = (!created_in_period.is_empty())
+        .then(|| created_in_period.iter().filter(|todo| todo.done).count() as f64 / created_in_period.len() as f64);
+
+    let mut open: Vec<(NaiveDate, &Todo)> = todos
+        .iter()
+        .filter(|todo| !todo.done)
+        .filter_map(|todo| dates::local_date(&todo.created_at).map(|date| (date, todo)))
+        .collect();
+    let average_open_age = (!open.is_empty())
+        .then(|| open.iter().map(|(date, _)| (today - *date).num_days() as f64).sum::<f64>() / open.len() as f64);
+    open.sort_by_key(|(date, todo)| (*date, todo.id));
+
+    let mut priorities = BTreeMap::new();
+    for todo in todos.iter().filter(|todo| !todo.done) {
+        *priorities.entry(todo.priority).or_insert(0) += 1;
+    }
+
+    Stats {
+        buckets,
+        completion_rate,
+        average_open_age,
+        oldest_open: open.into_iter().take(5).map(|(_, todo)| todo.clone()).collect(),
+        priorities,
+    }
+}
+
+// One block per value, as high as the value compared to the largest, e.g. `▁▄█▂`
+pub fn sparkline(values: &[usize]) -> String {
+    let max = values.iter().copied().max().unwrap_or(0);
+    values
+        .iter()
+        .map(|value| match (*value, max) {
+            (0, _) => ' ',
+            (value, max) => SPARKS[(value * (SPARKS.len() - 1)).div_ceil(max)],
+        })
+        .collect()
+}
+
+// A bar `width` blocks long for the largest value
+pub fn bar(value: usize, max: usize, width: usize) -> String {
+    if max == 0 {
+        return String::new();
+    }
+    "█".repeat((value * width).div_ceil(max))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn todo(id: i32, priority: i32, done: bool, created: (u32, u32), updated: (u32, u32)) -> Todo {
+        let timestamp = |(month, day): (u32, u32)| dates::to_timestamp(NaiveDate::from_ymd_opt(2026, month, day).unwrap().and_hms_opt(12, 0, 0).unwrap());
+        Todo {
+            done,
+            priority,
+            created_at: timestamp(created),
+            updated_at: timestamp(updated),
+            ..Todo::new(id, &format!("Todo {}", id))
+        }
+    }
+
+    #[test]
+    fn compute_stats() {
+        let todos = vec![
+            todo(1, 0, false, (9, 1), (9, 1)),
+            todo(2, 3, true, (10, 12), (10, 13)),
+            todo(3, 3, false, (10, 13), (10, 13)),
+            todo(4, 1, true, (10, 14), (10, 14)),
+            todo(5, 0, false, (10, 14), (10, 14)),
+        ];
+        // Monday 2026-10-12 to Wednesday 2026-10-14
+        let since = NaiveDate::from_ymd_opt(2026, 10, 12).unwrap();
+        let today = NaiveDate::from_ymd_opt(2026, 10, 14).unwrap();
+
+        let stats = compute(&todos, since, today, false);
+        let counts: Vec<(usize, usize)> = stats.buckets.iter().map(|bucket| (bucket.created, bucket.completed)).collect();
+        assert_eq!(counts, vec![(1, 0), (1, 1), (2, 1)]);
+        assert_eq!((stats.created(), stats.completed()), (4, 2));
+        assert_eq!(stats.completion_rate, Some(0.5));
+        assert_eq!(stats.average_open_age, Some((43.0 + 1.0 + 0.0) / 3.0));
+        assert_eq!(stats.oldest_open.iter().map(|todo| todo.id).collect::<Vec<_>>(), vec![1, 3, 5]);
+        assert_eq!(stats.priorities, BTreeMap::from([(0, 2), (3, 1)]));
+
+        let stats = compute(&todos, since, today, true);
+        assert_eq!(stats.buckets, vec![Bucket { start: since, created: 4, completed: 2 }]);
+    }
+
+    #[test]
+    fn charts() {
+        assert_eq!(sparkline(&[0, 1, 4, 8]), " ▂▅█");
+        assert_eq!(sparkline(&[0, 0]), "  ");
+        assert_eq!(bar(5, 10, 20), "█".repeat(10));
+        assert_eq!(bar(1, 10, 20), "██");
+        assert_eq!(bar(0, 0, 20), "");
+    }
+}
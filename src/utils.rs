@@ -14,3 +14,63 @@ pub fn user_input(text: &str) -> Result<String, ()> {
     io::stdin().read_line(&mut input).map_err(|_| ())?;
     Ok(input.trim().to_string())
 }
+
+// Formats seconds as e.g. `1h 05m`, `25m` or `40s`
+pub fn format_duration(seconds: i64) -> String {
+    let hours = seconds / 3600;
+    let minutes = seconds % 3600 / 60;
+    match (hours, minutes) {
+        (0, 0) => format!("{}s", seconds),
+        (0, _) => format!("{}m", minutes),
+        _ => format!("{}h {:02}m", hours, minutes),
+    }
+}
+
+// Lowercased with runs of whitespace collapsed, so `Buy  Milk` and `buy milk` compare equal
+pub fn normalize(text: &str) -> String {
+    text.split_whitespace().map(|word| word.to_lowercase()).collect::<Vec<String>>().join(" ")
+}
+
+// 1.0 for equal texts down to 0.0, based on the edit distance between them
+pub fn similarity(a: &str, b: &str) -> f64 {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    if a.is_empty() && b.is_empty() {
+        return 1.0;
+    }
+
+    // Levenshtein distance, keeping a single row of the table
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, a_char) in a.iter().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, b_char) in b.iter().enumerate() {
+            let substitution = diagonal + usize::from(a_char != b_char);
+            diagonal = row[j + 1];
+            row[j + 1] = substitution.min(row[j] + 1).min(row[j + 1] + 1);
+        }
+    }
+
+    1.0 - row[b.len()] as f64 / a.len().max(b.len()) as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn format_durations() {
+        assert_eq!(format_duration(40), "40s");
+        assert_eq!(format_duration(25 * 60 + 30), "25m");
+        assert_eq!(format_duration(3600 + 5 * 60), "1h 05m");
+    }
+
+    #[test]
+    fn similar_texts() {
+        assert_eq!(normalize("  Buy   Milk "), "buy milk");
+        assert_eq!(similarity("buy milk", "buy milk"), 1.0);
+        assert_eq!(similarity("kitten", "sitting"), 1.0 - 3.0 / 7.0);
+        assert!(similarity("buy milk", "buy milk!") > 0.85);
+        assert!(similarity("buy milk", "write report") < 0.5);
+    }
+}